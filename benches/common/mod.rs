@@ -0,0 +1,16 @@
+//! Representative ABR records, one per line like in the extract.
+
+pub const INDIVIDUAL: &str = r#"<ABR recordLastUpdatedDate="20200101" replaced="N"><ABN status="CAN" ABNStatusFromDate="20010101">51824753556</ABN><EntityType><EntityTypeInd>IND</EntityTypeInd><EntityTypeText>Individual/Sole Trader</EntityTypeText></EntityType><LegalEntity><IndividualName type="LGL"><NameTitle>MR</NameTitle><GivenName>JOHN</GivenName><GivenName>PAUL</GivenName><FamilyName>O'BRIEN</FamilyName></IndividualName><BusinessAddress><AddressDetails><State>VIC</State><Postcode>3000</Postcode></AddressDetails></BusinessAddress></LegalEntity><GST status="NON" GSTStatusFromDate="19000101" /><OtherEntity><NonIndividualName type="BN"><NonIndividualNameText>OBRIEN PLUMBING</NonIndividualNameText></NonIndividualName></OtherEntity></ABR>"#;
+
+pub const COMPANY_TRADE_NAMES: &str = r#"<ABR recordLastUpdatedDate="20231120" replaced="N"><ABN status="ACT" ABNStatusFromDate="20050301">53004085616</ABN><EntityType><EntityTypeInd>PRV</EntityTypeInd><EntityTypeText>Australian Private Company</EntityTypeText></EntityType><MainEntity><NonIndividualName type="MN"><NonIndividualNameText>SMITH &amp; SONS PTY LTD</NonIndividualNameText></NonIndividualName><BusinessAddress><AddressDetails><State>QLD</State><Postcode>4000</Postcode></AddressDetails></BusinessAddress></MainEntity><ASICNumber ASICNumberType="undetermined">004085616</ASICNumber><GST status="ACT" GSTStatusFromDate="20050301" /><OtherEntity><NonIndividualName type="TRD"><NonIndividualNameText>SMITH &amp; SONS</NonIndividualNameText></NonIndividualName></OtherEntity><OtherEntity><NonIndividualName type="TRD"><NonIndividualNameText>SMITH &amp; SONS BUILDERS</NonIndividualNameText></NonIndividualName></OtherEntity><OtherEntity><NonIndividualName type="TRD"><NonIndividualNameText>SMITH HOMES</NonIndividualNameText></NonIndividualName></OtherEntity><OtherEntity><NonIndividualName type="OTN"><NonIndividualNameText>SMITH BROTHERS</NonIndividualNameText></NonIndividualName></OtherEntity><OtherEntity><NonIndividualName type="OTN"><NonIndividualNameText>SMITH BROTHERS CONSTRUCTION</NonIndividualNameText></NonIndividualName></OtherEntity><OtherEntity><NonIndividualName type="BN"><NonIndividualNameText>SMITH &amp; SONS BUILDING</NonIndividualNameText></NonIndividualName></OtherEntity><OtherEntity><NonIndividualName type="BN"><NonIndividualNameText>SMITH &amp; SONS RENOVATIONS</NonIndividualNameText></NonIndividualName></OtherEntity><OtherEntity><NonIndividualName type="BN"><NonIndividualNameText>BRISBANE HOME EXTENSIONS</NonIndividualNameText></NonIndividualName></OtherEntity></ABR>"#;
+
+pub const DGR_CHARITY: &str = r#"<ABR recordLastUpdatedDate="20220815" replaced="N"><ABN status="ACT" ABNStatusFromDate="20000701">90749288305</ABN><EntityType><EntityTypeInd>PUB</EntityTypeInd><EntityTypeText>Australian Public Company</EntityTypeText></EntityType><MainEntity><NonIndividualName type="MN"><NonIndividualNameText>BRIGHT FUTURES FOUNDATION LIMITED</NonIndividualNameText></NonIndividualName><BusinessAddress><AddressDetails><State>NSW</State><Postcode>2000</Postcode></AddressDetails></BusinessAddress></MainEntity><ASICNumber ASICNumberType="undetermined">749288305</ASICNumber><GST status="ACT" GSTStatusFromDate="20000701" /><DGR status="ACT" DGRStatusFromDate="20010101"><NonIndividualName type="DGR"><NonIndividualNameText>BRIGHT FUTURES PUBLIC FUND</NonIndividualNameText></NonIndividualName></DGR><DGR status="ACT" DGRStatusFromDate="20150601"><NonIndividualName type="DGR"><NonIndividualNameText>BRIGHT FUTURES SCHOLARSHIP FUND</NonIndividualNameText></NonIndividualName></DGR><OtherEntity><NonIndividualName type="TRD"><NonIndividualNameText>BRIGHT FUTURES</NonIndividualNameText></NonIndividualName></OtherEntity></ABR>"#;
+
+pub const GST_CANCELLED: &str = r#"<ABR recordLastUpdatedDate="20190304" replaced="N"><ABN status="ACT" ABNStatusFromDate="20080512">46297207511</ABN><EntityType><EntityTypeInd>DIT</EntityTypeInd><EntityTypeText>Discretionary Investment Trust</EntityTypeText></EntityType><MainEntity><NonIndividualName type="MN"><NonIndividualNameText>THE TRUSTEE FOR THE NGUYEN FAMILY TRUST</NonIndividualNameText></NonIndividualName><BusinessAddress><AddressDetails><State>WA</State><Postcode>6000</Postcode></AddressDetails></BusinessAddress></MainEntity><GST status="CAN" GSTStatusFromDate="20190301" /></ABR>"#;
+
+pub const SHAPES: &[(&str, &str)] = &[
+    ("individual", INDIVIDUAL),
+    ("company_trade_names", COMPANY_TRADE_NAMES),
+    ("dgr_charity", DGR_CHARITY),
+    ("gst_cancelled", GST_CANCELLED),
+];
@@ -0,0 +1,34 @@
+use std::io;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use simple_abns::{
+    convert::{convert, ConvertArgs},
+    generate::{generate, GenerateOptions},
+};
+
+fn pipeline(c: &mut Criterion) {
+    // around 16 MB
+    let options = GenerateOptions {
+        records: 24_000,
+        ..Default::default()
+    };
+    let mut extract = Vec::new();
+    generate(&options, &mut extract).unwrap();
+    // far fewer lines than the default chunk size, so smaller chunks are used to parse several
+    // of them in parallel
+    let args = ConvertArgs {
+        chunk_size: Some(4_000),
+        ..Default::default()
+    };
+
+    let mut group = c.benchmark_group("convert");
+    group.throughput(Throughput::Bytes(extract.len() as u64));
+    group.sample_size(10);
+    group.bench_function("extract", |b| {
+        b.iter(|| convert(&extract[..], &mut io::sink(), &args).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, pipeline);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use simple_abns::parser::parse_record;
+
+mod common;
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_record");
+    for (name, record) in common::SHAPES {
+        group.throughput(Throughput::Bytes(record.len() as u64));
+        group.bench_function(*name, |b| b.iter(|| parse_record(record).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
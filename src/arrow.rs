@@ -0,0 +1,381 @@
+//! Arrow IPC output, for loading into Polars, pandas and the like without parsing. Records are
+//! written a batch per chunk, with the same types as the record model: dates as dates, enums
+//! dictionary encoded, and names as lists.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Seek, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use arrow_array::{
+    builder::{Date32Builder, Int8Builder, ListBuilder, StringBuilder, StructBuilder},
+    types::{Date32Type, Int8Type},
+    ArrayRef, DictionaryArray, RecordBatch, StringArray, StructArray,
+};
+use arrow_ipc::writer::{FileWriter, StreamWriter};
+use arrow_schema::{ArrowError, DataType, Field, Fields, Schema, SchemaRef};
+
+use crate::{
+    model::{ABNRecord, EntityName, EntityType, Status, TrustKind, SCHEMA_VERSION},
+    normalise::entity_match_key,
+    output::{Output, Written},
+};
+
+/// An Arrow IPC file (`.arrow` or `.feather`) or stream (`.arrows`) of records.
+pub struct ArrowWriter {
+    writer: Ipc,
+    schema: SchemaRef,
+    /// Every value of each enum, shared by all batches so dictionaries are only written once
+    statuses: ArrayRef,
+    entity_types: ArrayRef,
+    name_types: ArrayRef,
+    trust_kinds: ArrayRef,
+    match_key: bool,
+    display_name: bool,
+    batch: Batch,
+    records: u64,
+}
+
+enum Ipc {
+    File(FileWriter<BufWriter<File>>),
+    Stream(StreamWriter<BufWriter<File>>),
+}
+
+/// The columns of the records since the last batch, appended to as each record arrives. Enums
+/// are kept as their keys in the dictionary, which are their declared order.
+struct Batch {
+    len: usize,
+    abn: StringBuilder,
+    status: Int8Builder,
+    status_since: Date32Builder,
+    last_updated: Date32Builder,
+    name_type: Int8Builder,
+    title: StringBuilder,
+    given: StringBuilder,
+    given_2: StringBuilder,
+    family: StringBuilder,
+    name: StringBuilder,
+    hash: StringBuilder,
+    display_name: StringBuilder,
+    entity_type: Int8Builder,
+    /// Whether each record has a trust, as the struct's validity
+    trust: Vec<bool>,
+    trust_name: StringBuilder,
+    trust_kind: Int8Builder,
+    other_names: ListBuilder<StructBuilder>,
+    postcode: StringBuilder,
+    state: StringBuilder,
+    asic_number: StringBuilder,
+    gst_status: Int8Builder,
+    gst_status_since: Date32Builder,
+    match_key: StringBuilder,
+}
+
+impl ArrowWriter {
+    /// Creates the file, with `match_key` and `entity_name.display_name` columns if asked for.
+    pub fn create(path: &Path, match_key: bool, display_name: bool) -> Result<Self> {
+        let schema = Arc::new(schema(match_key, display_name));
+        let file = File::create(path).with_context(|| format!("failed to create {path:?}"))?;
+        let file = BufWriter::new(file);
+        let writer = match path.extension().and_then(|x| x.to_str()) {
+            Some("arrows") => Ipc::Stream(StreamWriter::try_new(file, &schema)?),
+            _ => Ipc::File(FileWriter::try_new(file, &schema)?),
+        };
+
+        let Ok(DataType::List(other_name)) = schema
+            .field_with_name("other_names")
+            .map(|x| x.data_type().clone())
+        else {
+            unreachable!("other_names is a list");
+        };
+        let DataType::Struct(other_name_fields) = other_name.data_type().clone() else {
+            unreachable!("other names are structs");
+        };
+        let other_names = StructBuilder::from_fields(other_name_fields, 0);
+        let batch = Batch {
+            len: 0,
+            abn: StringBuilder::new(),
+            status: Int8Builder::new(),
+            status_since: Date32Builder::new(),
+            last_updated: Date32Builder::new(),
+            name_type: Int8Builder::new(),
+            title: StringBuilder::new(),
+            given: StringBuilder::new(),
+            given_2: StringBuilder::new(),
+            family: StringBuilder::new(),
+            name: StringBuilder::new(),
+            hash: StringBuilder::new(),
+            display_name: StringBuilder::new(),
+            entity_type: Int8Builder::new(),
+            trust: Vec::new(),
+            trust_name: StringBuilder::new(),
+            trust_kind: Int8Builder::new(),
+            other_names: ListBuilder::new(other_names).with_field(other_name),
+            postcode: StringBuilder::new(),
+            state: StringBuilder::new(),
+            asic_number: StringBuilder::new(),
+            gst_status: Int8Builder::new(),
+            gst_status_since: Date32Builder::new(),
+            match_key: StringBuilder::new(),
+        };
+
+        let entity_types: Vec<_> = EntityType::ALL.iter().map(|x| format!("{x:?}")).collect();
+        Ok(Self {
+            writer,
+            schema,
+            statuses: Arc::new(StringArray::from(Status::ALL.map(Status::label).to_vec())),
+            entity_types: Arc::new(StringArray::from(entity_types)),
+            name_types: Arc::new(StringArray::from(EntityName::TYPES.to_vec())),
+            trust_kinds: Arc::new(StringArray::from(
+                TrustKind::ALL.map(TrustKind::label).to_vec(),
+            )),
+            match_key,
+            display_name,
+            batch,
+            records: 0,
+        })
+    }
+
+    /// Writes any records left and the end of the file.
+    pub fn finish(mut self) -> Result<Written> {
+        self.end_chunk()?;
+        let mut file = match self.writer {
+            Ipc::File(mut x) => {
+                x.finish()?;
+                x.into_inner()?
+            }
+            Ipc::Stream(mut x) => {
+                x.finish()?;
+                x.into_inner()?
+            }
+        };
+        file.flush()?;
+        Ok(Written {
+            bytes: file.stream_position()?,
+            records: self.records,
+        })
+    }
+
+    /// Takes the records appended so far as a batch, leaving the builders empty for the next.
+    fn record_batch(&mut self) -> Result<RecordBatch, ArrowError> {
+        let Some(DataType::Struct(name_fields)) = self.field("entity_name") else {
+            unreachable!("entity_name is a struct");
+        };
+        let Some(DataType::Struct(trust_fields)) = self.field("trust") else {
+            unreachable!("trust is a struct");
+        };
+        let batch = &mut self.batch;
+        let dictionary = |values: &ArrayRef, keys: &mut Int8Builder| -> Result<_, ArrowError> {
+            let array = DictionaryArray::<Int8Type>::try_new(keys.finish(), values.clone())?;
+            Ok(Arc::new(array) as ArrayRef)
+        };
+        let strings = |x: &mut StringBuilder| Arc::new(x.finish()) as ArrayRef;
+        let dates = |x: &mut Date32Builder| Arc::new(x.finish()) as ArrayRef;
+
+        let mut name = vec![
+            dictionary(&self.name_types, &mut batch.name_type)?,
+            strings(&mut batch.title),
+            strings(&mut batch.given),
+            strings(&mut batch.given_2),
+            strings(&mut batch.family),
+            strings(&mut batch.name),
+            strings(&mut batch.hash),
+        ];
+        if self.display_name {
+            name.push(strings(&mut batch.display_name));
+        }
+
+        let trust = StructArray::try_new(
+            trust_fields,
+            vec![
+                strings(&mut batch.trust_name),
+                dictionary(&self.trust_kinds, &mut batch.trust_kind)?,
+            ],
+            Some(batch.trust.drain(..).collect()),
+        )?;
+
+        let mut columns = vec![
+            strings(&mut batch.abn),
+            dictionary(&self.statuses, &mut batch.status)?,
+            dates(&mut batch.status_since),
+            dates(&mut batch.last_updated),
+            Arc::new(StructArray::try_new(name_fields, name, None)?),
+            dictionary(&self.entity_types, &mut batch.entity_type)?,
+            Arc::new(trust),
+            Arc::new(batch.other_names.finish()),
+            strings(&mut batch.postcode),
+            strings(&mut batch.state),
+            strings(&mut batch.asic_number),
+            dictionary(&self.statuses, &mut batch.gst_status)?,
+            dates(&mut batch.gst_status_since),
+        ];
+        if self.match_key {
+            columns.push(strings(&mut batch.match_key));
+        }
+        batch.len = 0;
+
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+
+    fn field(&self, name: &str) -> Option<DataType> {
+        let field = self.schema.field_with_name(name).ok()?;
+        Some(field.data_type().clone())
+    }
+}
+
+impl Output for ArrowWriter {
+    fn write_record(&mut self, record: &ABNRecord, _: &str) -> Result<()> {
+        let batch = &mut self.batch;
+        let date = Date32Type::from_naive_date;
+        batch.abn.append_value(&record.abn);
+        batch.status.append_value(record.status as i8);
+        batch.status_since.append_value(date(record.status_since));
+        batch.last_updated.append_value(date(record.last_updated));
+
+        let name = &record.entity_name;
+        let (title, given, given_2, family, entity_name, hash) = match name {
+            EntityName::Individual {
+                title,
+                given,
+                given_2,
+                family,
+            } => (
+                title.as_deref(),
+                given.as_deref(),
+                given_2.as_deref(),
+                Some(family.as_str()),
+                None,
+                None,
+            ),
+            EntityName::NonIndividual { name } => {
+                (None, None, None, None, Some(name.as_str()), None)
+            }
+            EntityName::Redacted { hash } => (None, None, None, None, None, hash.as_deref()),
+        };
+        batch.name_type.append_value(name.type_index() as i8);
+        batch.title.append_option(title);
+        batch.given.append_option(given);
+        batch.given_2.append_option(given_2);
+        batch.family.append_option(family);
+        batch.name.append_option(entity_name);
+        batch.hash.append_option(hash);
+        if self.display_name {
+            batch.display_name.append_value(name.display_name());
+        }
+        batch.entity_type.append_value(record.entity_type as i8);
+
+        let trust = record.trust.as_ref();
+        batch.trust.push(trust.is_some());
+        batch.trust_name.append_option(trust.map(|x| &x.name));
+        batch.trust_kind.append_option(trust.map(|x| x.kind as i8));
+
+        let names = batch.other_names.values();
+        for other_name in &record.other_names {
+            for (i, value) in [other_name.name.as_str(), other_name.kind.code()]
+                .into_iter()
+                .enumerate()
+            {
+                names
+                    .field_builder::<StringBuilder>(i)
+                    .expect("other name fields are strings")
+                    .append_value(value);
+            }
+            names.append(true);
+        }
+        batch.other_names.append(true);
+
+        batch.postcode.append_option(record.postcode.as_ref());
+        batch.state.append_option(record.state.as_ref());
+        batch.asic_number.append_option(record.asic_number.as_ref());
+        batch
+            .gst_status
+            .append_option(record.gst_status.map(|x| x as i8));
+        batch
+            .gst_status_since
+            .append_option(record.gst_status_since.map(date));
+        if self.match_key {
+            batch.match_key.append_value(entity_match_key(name));
+        }
+        batch.len += 1;
+        Ok(())
+    }
+
+    fn end_chunk(&mut self) -> Result<()> {
+        if self.batch.len == 0 {
+            return Ok(());
+        }
+        let records = self.batch.len as u64;
+        let batch = self.record_batch()?;
+        match &mut self.writer {
+            Ipc::File(x) => x.write(&batch)?,
+            Ipc::Stream(x) => x.write(&batch)?,
+        }
+        self.records += records;
+        Ok(())
+    }
+}
+
+/// The Arrow schema of records, which follows the JSON layout. Its metadata has the versions of
+/// simple-abns and the record schema, under the same names as in manifests.
+pub fn schema(match_key: bool, display_name: bool) -> Schema {
+    let dictionary = |key| DataType::Dictionary(Box::new(key), Box::new(DataType::Utf8));
+    let string = |name, nullable| Field::new(name, DataType::Utf8, nullable);
+    let date = |name, nullable| Field::new(name, DataType::Date32, nullable);
+
+    let mut entity_name = vec![
+        Field::new("type", dictionary(DataType::Int8), false),
+        string("title", true),
+        string("given", true),
+        string("given_2", true),
+        string("family", true),
+        string("name", true),
+        string("hash", true),
+    ];
+    if display_name {
+        entity_name.push(string("display_name", false));
+    }
+    let trust = Fields::from(vec![
+        string("name", false),
+        Field::new("kind", dictionary(DataType::Int8), false),
+    ]);
+    let other_name = Fields::from(vec![string("name", false), string("kind", false)]);
+
+    let mut fields = vec![
+        string("abn", false),
+        Field::new("status", dictionary(DataType::Int8), false),
+        date("status_since", false),
+        date("last_updated", false),
+        Field::new("entity_name", DataType::Struct(entity_name.into()), false),
+        Field::new("entity_type", dictionary(DataType::Int8), false),
+        Field::new("trust", DataType::Struct(trust), true),
+        Field::new(
+            "other_names",
+            DataType::List(Arc::new(Field::new(
+                "item",
+                DataType::Struct(other_name),
+                false,
+            ))),
+            false,
+        ),
+        string("postcode", true),
+        string("state", true),
+        string("asic_number", true),
+        Field::new("gst_status", dictionary(DataType::Int8), true),
+        date("gst_status_since", true),
+    ];
+    if match_key {
+        fields.push(string("match_key", false));
+    }
+    let metadata = HashMap::from([
+        (
+            "simple_abns_version".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        ),
+        ("schema_version".to_string(), SCHEMA_VERSION.to_string()),
+    ]);
+    Schema::new_with_metadata(fields, metadata)
+}
@@ -0,0 +1,115 @@
+//! Where a conversion had got to, saved next to its output so a conversion that dies part way
+//! through can be resumed instead of started over.
+
+use std::{
+    fmt::Debug,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::output::Written;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// What the conversion was started with, which has to be the same to resume it
+    pub fingerprint: Fingerprint,
+    /// The input file being converted, or the last one finished
+    pub input: String,
+    /// Bytes of the input file that have been converted
+    pub offset: u64,
+    /// Lines of the input file that have been converted
+    pub line: u64,
+    /// The output written up to this point
+    pub output: Written,
+}
+
+/// The options and input files a conversion was started with. Resuming with anything else
+/// would leave an output that's half one thing and half another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Hex SHA-256 of the options that change what's written
+    pub options: String,
+    pub inputs: Vec<InputFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputFile {
+    pub path: String,
+    /// Size and modification time of the file, or none if it didn't exist
+    pub bytes: Option<u64>,
+    pub modified: Option<SystemTime>,
+}
+
+impl Checkpoint {
+    /// Where the checkpoint of an output file is kept, e.g. `out.jsonl.zst.checkpoint`.
+    pub fn path(output: &Path) -> PathBuf {
+        let mut path = output.as_os_str().to_owned();
+        path.push(".checkpoint");
+        path.into()
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let checkpoint =
+            fs::read_to_string(path).with_context(|| format!("no checkpoint at {path:?}"))?;
+        serde_json::from_str(&checkpoint).with_context(|| format!("invalid checkpoint {path:?}"))
+    }
+
+    /// Replaces the checkpoint in one step, so there's always a whole one to resume from.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let mut file = File::create(&temp)?;
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+        // on disk before it replaces the old one, or a crash could leave neither
+        file.sync_all()?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
+
+impl Fingerprint {
+    /// Hashes `options` by their debug output, and looks up the size and modification time of
+    /// each input.
+    pub fn new(options: &impl Debug, inputs: &[String]) -> Self {
+        let options = Sha256::digest(format!("{options:?}"))
+            .iter()
+            .map(|x| format!("{x:02x}"))
+            .collect();
+        let inputs = inputs
+            .iter()
+            .map(|path| {
+                let metadata = fs::metadata(path).ok();
+                InputFile {
+                    path: path.clone(),
+                    bytes: metadata.as_ref().map(|x| x.len()),
+                    modified: metadata.and_then(|x| x.modified().ok()),
+                }
+            })
+            .collect();
+        Self { options, inputs }
+    }
+
+    /// Fails if a conversion started with `self` can't be resumed as `current`.
+    pub fn check(&self, current: &Fingerprint) -> Result<()> {
+        ensure!(
+            self.options == current.options,
+            "the options that change the output aren't the same as when it was started"
+        );
+        ensure!(
+            self.inputs.len() == current.inputs.len(),
+            "the input files aren't the same as when it was started"
+        );
+        for (saved, current) in self.inputs.iter().zip(&current.inputs) {
+            if saved != current {
+                bail!("{} has changed since it was started", current.path);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,610 @@
+use itertools::Itertools;
+use rayon::{prelude::*, ThreadPoolBuilder};
+use std::io::{self, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::{
+    env,
+    fs::{self, File},
+    io::BufRead,
+    iter, mem,
+    path::PathBuf,
+    sync::mpsc::{self, SyncSender},
+    thread,
+};
+
+use anyhow::{ensure, Context, Error, Result};
+use clap::Args;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    arrow::ArrowWriter,
+    checkpoint::{Checkpoint, Fingerprint},
+    filter::Filter,
+    manifest::{self, Manifest},
+    model::ABNRecord,
+    normalise::entity_match_key,
+    output::{CompressionArgs, Format, Output, ShardKey, Shards, Writer},
+    parser,
+    postgres::PostgresWriter,
+    privacy::{PrivacyArgs, Redaction, Redactor},
+    progress::{Progress, ProgressArgs},
+    projection::Projection,
+};
+
+#[derive(Debug, Default, Args)]
+pub struct ConvertArgs {
+    #[command(flatten)]
+    pub filter: Filter,
+    #[command(flatten)]
+    pub privacy: PrivacyArgs,
+    /// Only output these comma separated fields, e.g. abn,entity_name.family:surname,state
+    #[arg(long)]
+    pub fields: Option<Projection>,
+    /// Write business_names and trade_names lists instead of other_names, like older versions
+    #[arg(long)]
+    pub legacy_names: bool,
+    /// Add a match_key field with the entity name normalised for matching, e.g. SMITH AND SONS PTY LTD
+    #[arg(long)]
+    pub match_key: bool,
+    /// Add a display_name field to entity_name, with individuals' names title cased, e.g. Dr Mary McDonald
+    #[arg(long)]
+    pub display_name: bool,
+    /// Write to this file instead of stdout, compressed if it ends in .zst or .gz, and as CSV if
+    /// it ends in .csv before that. .arrow and .feather files are written as Arrow IPC files,
+    /// and .arrows as an Arrow IPC stream. .sql is written as a psql script that creates tables
+    /// and loads them from COPY data files next to it. With --shard-by, {} in the path is replaced by each
+    /// shard's name, e.g. out/{}.jsonl.zst
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+    /// Split the output into a file for each state, type, status or abn (first digit)
+    #[arg(long, requires = "output")]
+    pub shard_by: Option<ShardKey>,
+    #[command(flatten)]
+    pub compression: CompressionArgs,
+    #[command(flatten)]
+    pub progress: ProgressArgs,
+    /// Threads to parse records with, by default one per core
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Lines to read from a file before parsing them in parallel, 65535 by default
+    #[arg(long)]
+    pub chunk_size: Option<usize>,
+    /// Carry on from where a conversion to --output stopped, as saved in its .checkpoint file
+    #[arg(long, requires = "output", conflicts_with = "shard_by")]
+    pub resume: bool,
+    /// Write a JSON manifest of the input and output files' sizes and hashes, the number of
+    /// records written and the versions used, e.g. simple-abns.manifest.json
+    #[arg(long, requires = "output")]
+    pub manifest: Option<PathBuf>,
+    /// Write the COPY data files of .sql output in PostgreSQL's binary format instead of text
+    #[arg(long, requires = "output")]
+    pub copy_binary: bool,
+    /// Set from the output file's name
+    #[arg(skip)]
+    pub format: Format,
+}
+
+const CHUNK_SIZE: usize = 65535;
+const CHECKPOINT_LINES: u64 = 1 << 20;
+
+/// Columns of CSV output when --fields isn't given, flattening names into one column each.
+const CSV_FIELDS: &str = "abn,status,status_since,last_updated,entity_type,\
+    entity_name.type:name_type,entity_name.name:name,entity_name.title:title,\
+    entity_name.given:given,entity_name.given_2:given_2,entity_name.family:family,\
+    trust.name:trust_name,trust.kind:trust_kind";
+
+pub fn run(mut args: ConvertArgs) -> Result<()> {
+    if let Some(path) = &args.output {
+        args.format = Format::from_path(path)?;
+    }
+    if let Some(fields) = &args.fields {
+        fields.check_names(args.legacy_names)?;
+    }
+    let header = match args.format {
+        Format::Csv => Some(csv_row(csv_fields(&args)?.columns())?),
+        Format::Json => None,
+        Format::Arrow | Format::Postgres => {
+            let format = match args.format {
+                Format::Arrow => "Arrow",
+                _ => "PostgreSQL",
+            };
+            ensure!(
+                args.fields.is_none() && !args.legacy_names,
+                "{format} output has the record's own fields, so --fields and --legacy-names don't apply"
+            );
+            ensure!(
+                args.shard_by.is_none() && !args.resume,
+                "{format} output can't be sharded or resumed"
+            );
+            ensure!(
+                args.compression.compression_level.is_none()
+                    && args.compression.zstd_threads.is_none(),
+                "{format} output can't be compressed, so --compression-level and --zstd-threads don't apply"
+            );
+            None
+        }
+    };
+    ensure!(
+        !args.copy_binary || args.format == Format::Postgres,
+        "--copy-binary only applies to .sql output"
+    );
+
+    let outputs = match (&args.output, args.shard_by) {
+        (Some(path), None) if args.format == Format::Arrow => {
+            let mut output = ArrowWriter::create(path, args.match_key, args.display_name)?;
+            convert_all(&mut output, &args, None)?;
+            vec![(path.clone(), output.finish()?)]
+        }
+        (Some(path), None) if args.format == Format::Postgres => {
+            let mut output =
+                PostgresWriter::create(path, args.copy_binary, args.match_key, args.display_name)?;
+            convert_all(&mut output, &args, None)?;
+            output.finish()?
+        }
+        (Some(path), Some(key)) => {
+            let path = path.to_str().context("output path isn't valid UTF-8")?;
+            let mut output = Shards::new(path, key, args.compression, header)?;
+            convert_all(&mut output, &args, None)?;
+            output.finish()?
+        }
+        (Some(path), None) => {
+            let threads = match args.compression.zstd_threads {
+                Some(x) => x,
+                None => thread::available_parallelism().map_or(1, |x| x.get() as u32),
+            };
+            let checkpoint = Checkpoint::path(path);
+            let written = if args.resume {
+                let resume = Checkpoint::load(&checkpoint)?;
+                resume
+                    .fingerprint
+                    .check(&fingerprint(&args)?)
+                    .with_context(|| format!("{path:?} can't be resumed"))?;
+                let mut output = Writer::append(path, args.compression, threads, resume.output)?;
+                convert_all(&mut output, &args, Some(&resume))?;
+                output.finish()?
+            } else {
+                // an old checkpoint would be for an output that's about to be replaced
+                if checkpoint.exists() {
+                    fs::remove_file(&checkpoint)?;
+                }
+                let mut output =
+                    Writer::create(path, args.compression, threads, header.as_deref())?;
+                convert_all(&mut output, &args, None)?;
+                output.finish()?
+            };
+            if checkpoint.exists() {
+                fs::remove_file(checkpoint)?;
+            }
+            vec![(path.clone(), written)]
+        }
+        (None, _) => {
+            let mut output = BufWriter::new(io::stdout().lock());
+            convert_all(&mut output, &args, None)?;
+            output.flush()?;
+            Vec::new()
+        }
+    };
+
+    if let Some(path) = &args.manifest {
+        let manifest = Manifest::new(&input_paths(), &outputs, env::args().collect())?;
+        let manifest = serde_json::to_string_pretty(&manifest)? + "\n";
+        fs::write(path, manifest).with_context(|| format!("failed to write {path:?}"))?;
+    }
+
+    Ok(())
+}
+
+/// The extract's files, in order.
+fn input_paths() -> Vec<String> {
+    (1..=20)
+        .map(|i| format!("raw/20241127_Public{i:02}.xml"))
+        .collect()
+}
+
+/// What a conversion is started with, for checking it's the same when resuming. Only options
+/// that change what's written are included, so threads and chunk sizes can differ.
+fn fingerprint(args: &ConvertArgs) -> Result<Fingerprint> {
+    let key = match &args.privacy.hash_key_file {
+        Some(path) => Some(manifest::sha256(path)?.1),
+        None => None,
+    };
+    let options = (
+        env!("CARGO_PKG_VERSION"),
+        args.format,
+        &args.filter,
+        &args.privacy,
+        key,
+        &args.fields,
+        args.legacy_names,
+        args.match_key,
+        args.display_name,
+        args.compression.compression_level,
+    );
+    Ok(Fingerprint::new(&options, &input_paths()))
+}
+
+fn convert_all(
+    output: &mut impl Output,
+    args: &ConvertArgs,
+    resume: Option<&Checkpoint>,
+) -> Result<()> {
+    let paths = input_paths();
+    // missing files count as empty here, and fail when they're reached
+    let sizes: Vec<_> = paths
+        .iter()
+        .map(|x| fs::metadata(x).map_or(0, |x| x.len()))
+        .collect();
+    let mut progress = Progress::new(&args.progress, sizes.iter().sum());
+
+    let (first, offset, line) = match resume {
+        Some(resume) => {
+            let first = paths
+                .iter()
+                .position(|x| *x == resume.input)
+                .with_context(|| format!("checkpoint is for unknown input {}", resume.input))?;
+            (first, resume.offset, resume.line)
+        }
+        None => (0, 0, 0),
+    };
+    progress.skip(sizes[..first].iter().sum::<u64>() + offset);
+
+    let counter = progress.counter();
+    let inputs = paths.into_iter().enumerate().skip(first).map(|(i, path)| {
+        let mut file = File::open(&path).with_context(|| format!("failed to open {path}"))?;
+        let (offset, line) = if i == first { (offset, line) } else { (0, 0) };
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(Input {
+            offset,
+            line,
+            ..Input::new(path, BufReader::new(counter.wrap(file)))
+        })
+    });
+    convert_inputs(inputs, output, args, &mut progress)?;
+    progress.finish();
+
+    Ok(())
+}
+
+/// An input file, or the rest of one when resuming.
+pub struct Input<R> {
+    pub name: String,
+    pub reader: R,
+    /// Bytes of the file before `reader`, which have already been converted
+    pub offset: u64,
+    /// Lines of the file before `reader`
+    pub line: u64,
+}
+
+impl<R> Input<R> {
+    pub fn new(name: impl Into<String>, reader: R) -> Self {
+        Self {
+            name: name.into(),
+            reader,
+            offset: 0,
+            line: 0,
+        }
+    }
+}
+
+/// Converts one chunk of the extract, writing a JSON line (or CSV row) for each record.
+pub fn convert(
+    input: impl BufRead + Send,
+    output: &mut impl Output,
+    args: &ConvertArgs,
+) -> Result<()> {
+    let inputs = iter::once(Ok(Input::new("", input)));
+    convert_inputs(inputs, output, args, &mut Progress::hidden())
+}
+
+/// Converts chunks of the extract one after another, opening each as it's reached.
+///
+/// Reading, parsing and writing run at the same time, so one file is read while the last is
+/// still being parsed. Records are always written in the order they were read, whatever the
+/// thread count and chunk size.
+///
+/// With `args.output` set, a checkpoint is saved next to it at the end of each file and every
+/// million or so lines, if `output` can be resumed.
+pub fn convert_inputs<I: BufRead>(
+    inputs: impl Iterator<Item = Result<Input<I>>> + Send,
+    output: &mut impl Output,
+    args: &ConvertArgs,
+    progress: &mut Progress,
+) -> Result<()> {
+    let redactor = Redactor::new(&args.privacy)?;
+    let csv = match args.format {
+        Format::Csv => Some(csv_fields(args)?),
+        Format::Json | Format::Arrow | Format::Postgres => None,
+    };
+    let chunk_size = args.chunk_size.unwrap_or(CHUNK_SIZE);
+    ensure!(chunk_size > 0, "--chunk-size must be at least 1");
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(args.threads.unwrap_or(0))
+        .build()?;
+    let checkpoint = match &args.output {
+        Some(path) => Some((Checkpoint::path(path), fingerprint(args)?)),
+        None => None,
+    };
+
+    let (pool, redactor, csv) = (&pool, &redactor, csv.as_ref());
+    thread::scope(|scope| {
+        // a couple of chunks can wait between each stage, to smooth out slow ones
+        let (chunks, read) = mpsc::sync_channel(2);
+        let (converted, parsed) = mpsc::sync_channel(2);
+        scope.spawn(move || {
+            if let Err(e) = read_chunks(inputs, chunk_size, &chunks) {
+                let _ = chunks.send(Err(e));
+            }
+        });
+        scope.spawn(move || {
+            for chunk in read {
+                let chunk = chunk.and_then(|mut chunk: Chunk| {
+                    let lines = mem::take(&mut chunk.lines);
+                    let records = pool.install(|| {
+                        lines
+                            .par_iter()
+                            .map(|x| convert_record(x, args, redactor, csv))
+                            .collect::<Result<Vec<_>>>()
+                    })?;
+                    Ok((chunk, records))
+                });
+                if converted.send(chunk).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut file = None;
+        let mut unsaved = 0;
+        for chunk in parsed {
+            let (chunk, records) = chunk?;
+            if file != Some(chunk.file) {
+                progress.start_file(&chunk.name);
+                file = Some(chunk.file);
+            }
+            for record in &records {
+                match record {
+                    Converted::Record(record, line) => output.write_record(record, line)?,
+                    Converted::Skipped => (),
+                    Converted::Invalid(e) => progress.error(e),
+                }
+            }
+            output.end_chunk()?;
+            progress.records(records.len() as u64);
+
+            unsaved += chunk.lines_read;
+            let Some((path, fingerprint)) = &checkpoint else {
+                continue;
+            };
+            // frames are ended at each checkpoint, so they're kept far enough apart to not
+            // hurt compression, and at the same places every time so output is reproducible
+            if chunk.last || unsaved >= CHECKPOINT_LINES {
+                if let Some(written) = output.checkpoint()? {
+                    let checkpoint = Checkpoint {
+                        fingerprint: fingerprint.clone(),
+                        input: chunk.name,
+                        offset: chunk.offset,
+                        line: chunk.line,
+                        output: written,
+                    };
+                    checkpoint.save(path)?;
+                }
+                unsaved = 0;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// Lines of one input file to be parsed together.
+struct Chunk {
+    file: usize,
+    name: String,
+    lines: Vec<String>,
+    /// Lines read for this chunk, including any that were skipped
+    lines_read: u64,
+    /// Bytes and lines of the file up to the end of this chunk
+    offset: u64,
+    line: u64,
+    /// Whether this chunk finishes the file
+    last: bool,
+}
+
+enum Converted {
+    Record(Box<ABNRecord>, String),
+    /// Filtered out
+    Skipped,
+    Invalid(Error),
+}
+
+fn read_chunks<I: BufRead>(
+    inputs: impl Iterator<Item = Result<Input<I>>>,
+    chunk_size: usize,
+    chunks: &SyncSender<Result<Chunk>>,
+) -> Result<()> {
+    for (file, input) in inputs.enumerate() {
+        let Input {
+            name,
+            mut reader,
+            mut offset,
+            mut line,
+        } = input?;
+        loop {
+            let mut lines = Vec::new();
+            let mut lines_read = 0;
+            let mut text = String::new();
+            while lines_read < chunk_size as u64 {
+                text.clear();
+                let bytes = reader.read_line(&mut text)?;
+                if bytes == 0 {
+                    break;
+                }
+                offset += bytes as u64;
+                line += 1;
+                lines_read += 1;
+                let text = text.strip_suffix('\n').unwrap_or(&text);
+                let text = text.strip_suffix('\r').unwrap_or(text);
+                if !(line <= 4 || text == "</Transfer>") {
+                    lines.push(text.to_string());
+                }
+            }
+            if lines_read == 0 {
+                break;
+            }
+            let chunk = Chunk {
+                file,
+                name: name.clone(),
+                lines,
+                lines_read,
+                offset,
+                line,
+                last: reader.fill_buf()?.is_empty(),
+            };
+            let last = chunk.last;
+            // the writer has stopped, and will report why
+            if chunks.send(Ok(chunk)).is_err() {
+                return Ok(());
+            }
+            if last {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses, filters, redacts and renders a record. Records that can't be parsed are passed on to
+/// be reported in order, while failing to render one stops the conversion.
+fn convert_record(
+    text: &str,
+    args: &ConvertArgs,
+    redactor: &Redactor,
+    csv: Option<&Projection>,
+) -> Result<Converted> {
+    Ok(match parser::parse_record(text) {
+        Ok(mut x) if args.filter.matches(&x) => {
+            redactor.apply(&mut x);
+            let line = match args.format {
+                // Arrow batches are built from the records themselves
+                Format::Arrow | Format::Postgres => String::new(),
+                Format::Json | Format::Csv => line(&x, args, csv)?,
+            };
+            Converted::Record(Box::new(x), line)
+        }
+        Ok(_) => Converted::Skipped,
+        Err(e) => Converted::Invalid(e),
+    })
+}
+
+fn line(record: &ABNRecord, args: &ConvertArgs, csv: Option<&Projection>) -> Result<String> {
+    // extra fields are added when asked for, or when selected with --fields
+    let fields = csv.or(args.fields.as_ref());
+    let selected = |path: &[&str]| fields.is_some_and(|x| x.includes(path));
+    let match_key = args.match_key || selected(&["match_key"]);
+    let display_name = args.display_name || selected(&["entity_name", "display_name"]);
+    if !args.legacy_names && !match_key && !display_name {
+        return render(record, args.fields.as_ref(), csv);
+    }
+
+    let mut value = if args.legacy_names {
+        legacy_names(record)?
+    } else {
+        serde_json::to_value(record)?
+    };
+    if match_key {
+        value["match_key"] = entity_match_key(&record.entity_name).into();
+    }
+    if display_name {
+        value["entity_name"]["display_name"] = record.entity_name.display_name().into();
+    }
+    render(&value, args.fields.as_ref(), csv)
+}
+
+fn render(
+    record: &impl Serialize,
+    fields: Option<&Projection>,
+    csv: Option<&Projection>,
+) -> Result<String> {
+    Ok(match (csv, fields) {
+        (Some(columns), _) => csv_row(columns.row(record)?.iter().map(cell))?,
+        (None, Some(fields)) => serde_json::to_string(&fields.apply(record)?)?,
+        (None, None) => serde_json::to_string(record)?,
+    })
+}
+
+/// The columns of CSV output: --fields if given, or every field with names flattened and any
+/// extra fields asked for on the end.
+fn csv_fields(args: &ConvertArgs) -> Result<Projection> {
+    if let Some(fields) = &args.fields {
+        return Ok(fields.clone());
+    }
+
+    let mut fields = CSV_FIELDS.to_string();
+    if args.legacy_names {
+        fields += ",business_names,trade_names";
+    } else {
+        fields += ",other_names.name:other_names,other_names.kind:other_name_kinds";
+    }
+    fields += ",postcode,state,asic_number,gst_status,gst_status_since";
+    if args.privacy.redact_individuals == Some(Redaction::Hash) {
+        fields += ",entity_name.hash:name_hash";
+    }
+    if args.display_name {
+        fields += ",entity_name.display_name:display_name";
+    }
+    if args.match_key {
+        fields += ",match_key";
+    }
+    fields.parse()
+}
+
+/// A CSV cell for a field: nulls are empty, lists are joined with "; ", and anything else
+/// nested is left as JSON.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(x) => x.clone(),
+        Value::Array(x) => x.iter().map(cell).join("; "),
+        x => x.to_string(),
+    }
+}
+
+fn csv_row(cells: impl IntoIterator<Item = String>) -> Result<String> {
+    let mut row = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    row.write_record(cells)?;
+    let mut row = String::from_utf8(row.into_inner()?)?;
+    row.pop();
+    Ok(row)
+}
+
+/// The record as older versions wrote it, with other names split into `business_names` and
+/// `trade_names` lists in place of `other_names`. Names of other types are left out.
+pub fn legacy_names(record: &ABNRecord) -> Result<Value> {
+    let Value::Object(fields) = serde_json::to_value(record)? else {
+        unreachable!("records serialize as objects");
+    };
+
+    let mut output = Map::new();
+    for (key, value) in fields {
+        if key == "other_names" {
+            continue;
+        }
+        let entity_type = key == "entity_type";
+        output.insert(key, value);
+        if entity_type {
+            let business_names: Vec<_> = record.business_names().collect();
+            if !business_names.is_empty() {
+                output.insert("business_names".into(), business_names.into());
+            }
+            let trade_names: Vec<_> = record.trade_names().collect();
+            if !trade_names.is_empty() {
+                output.insert("trade_names".into(), trade_names.into());
+            }
+        }
+    }
+
+    Ok(Value::Object(output))
+}
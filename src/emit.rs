@@ -0,0 +1,172 @@
+//! Writes records back out in the ABR's XML layout, for fixtures and systems that only read the
+//! extract. Anything the record doesn't keep, like DGR funds, can't be written back.
+
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use clap::Args;
+
+use crate::{
+    model::{ABNRecord, EntityName, Status},
+    privacy::{PrivacyArgs, Redactor},
+    xml::escape,
+};
+
+#[derive(Debug, Args)]
+pub struct XmlArgs {
+    /// JSON lines file written by convert
+    pub data: PathBuf,
+    #[command(flatten)]
+    pub privacy: PrivacyArgs,
+}
+
+/// Converts JSON lines back into an extract, with the same header and footer lines as `gen`.
+pub fn run(args: XmlArgs) -> Result<()> {
+    let redactor = Redactor::new(&args.privacy)?;
+    let input = BufReader::new(File::open(&args.data)?);
+    let mut output = BufWriter::new(io::stdout().lock());
+
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(output, "<Transfer>")?;
+    writeln!(output, "<extractTime>20241127</extractTime>")?;
+    writeln!(output, "<extractType>Full</extractType>")?;
+    for (i, line) in input.lines().enumerate() {
+        let mut record: ABNRecord = serde_json::from_str(&line?)
+            .with_context(|| format!("line {} of {:?}", i + 1, args.data))?;
+        redactor.apply(&mut record);
+        writeln!(output, "{}", emit_record(&record))?;
+    }
+    writeln!(output, "</Transfer>")?;
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Writes a single line `<ABR>` element that `parse_record` reads back as the same record.
+///
+/// Redacted names are the exception, as the extract has no way to leave a name out. They're
+/// written as an individual whose family name is the hash, or `REDACTED` without one, so they
+/// read back as `EntityName::Individual` and not as redacted.
+pub fn emit_record(record: &ABNRecord) -> String {
+    let mut x = String::new();
+    let _ = write!(
+        x,
+        r#"<ABR recordLastUpdatedDate="{}" replaced="N"><ABN status="{}" ABNStatusFromDate="{}">{}</ABN>"#,
+        date(record.last_updated),
+        status_code(record.status),
+        date(record.status_since),
+        escape(&record.abn),
+    );
+    let _ = write!(
+        x,
+        "<EntityType><EntityTypeInd>{:?}</EntityTypeInd><EntityTypeText>{}</EntityTypeText></EntityType>",
+        record.entity_type,
+        escape(record.entity_type.description()),
+    );
+
+    match &record.entity_name {
+        EntityName::Individual {
+            title,
+            given,
+            given_2,
+            family,
+        } => {
+            x.push_str(r#"<LegalEntity><IndividualName type="LGL">"#);
+            if let Some(title) = title {
+                let _ = write!(x, "<NameTitle>{}</NameTitle>", escape(title));
+            }
+            for given in [given, given_2].into_iter().flatten() {
+                let _ = write!(x, "<GivenName>{}</GivenName>", escape(given));
+            }
+            let _ = write!(
+                x,
+                "<FamilyName>{}</FamilyName></IndividualName>",
+                escape(family)
+            );
+            address(&mut x, record);
+            x.push_str("</LegalEntity>");
+        }
+        EntityName::Redacted { hash } => {
+            let _ = write!(
+                x,
+                r#"<LegalEntity><IndividualName type="LGL"><FamilyName>{}</FamilyName></IndividualName>"#,
+                escape(hash.as_deref().unwrap_or("REDACTED"))
+            );
+            address(&mut x, record);
+            x.push_str("</LegalEntity>");
+        }
+        EntityName::NonIndividual { name } => {
+            let _ = write!(
+                x,
+                r#"<MainEntity><NonIndividualName type="MN"><NonIndividualNameText>{}</NonIndividualNameText></NonIndividualName>"#,
+                escape(name),
+            );
+            address(&mut x, record);
+            x.push_str("</MainEntity>");
+        }
+    }
+
+    if let Some(acn) = &record.asic_number {
+        let _ = write!(
+            x,
+            r#"<ASICNumber ASICNumberType="undetermined">{}</ASICNumber>"#,
+            escape(acn)
+        );
+    }
+
+    match (record.gst_status, record.gst_status_since) {
+        (Some(status), Some(since)) => {
+            let _ = write!(
+                x,
+                r#"<GST status="{}" GSTStatusFromDate="{}" />"#,
+                status_code(status),
+                date(since)
+            );
+        }
+        _ => x.push_str(r#"<GST status="NON" GSTStatusFromDate="19000101" />"#),
+    }
+
+    for name in &record.other_names {
+        let _ = write!(
+            x,
+            r#"<OtherEntity><NonIndividualName type="{}"><NonIndividualNameText>{}</NonIndividualNameText></NonIndividualName></OtherEntity>"#,
+            escape(name.kind.code()),
+            escape(&name.name),
+        );
+    }
+
+    x.push_str("</ABR>");
+    x
+}
+
+fn address(x: &mut String, record: &ABNRecord) {
+    if record.state.is_none() && record.postcode.is_none() {
+        return;
+    }
+
+    x.push_str("<BusinessAddress><AddressDetails>");
+    if let Some(state) = &record.state {
+        let _ = write!(x, "<State>{}</State>", escape(state));
+    }
+    if let Some(postcode) = &record.postcode {
+        let _ = write!(x, "<Postcode>{}</Postcode>", escape(postcode));
+    }
+    x.push_str("</AddressDetails></BusinessAddress>");
+}
+
+fn status_code(status: Status) -> &'static str {
+    match status {
+        Status::Active => "ACT",
+        Status::Cancelled => "CAN",
+    }
+}
+
+fn date(date: NaiveDate) -> impl std::fmt::Display {
+    date.format("%Y%m%d")
+}
@@ -0,0 +1,127 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure, Error, Result};
+use chrono::NaiveDate;
+use clap::Args;
+
+use crate::model::{ABNRecord, EntityCategory, EntityType, Status};
+
+#[derive(Debug, Default, Args)]
+pub struct Filter {
+    /// Only include records with this ABN status, active or cancelled in any case
+    #[arg(long)]
+    pub status: Option<Status>,
+    /// Only include records with this GST status, active, cancelled or none in any case
+    #[arg(long)]
+    pub gst_status: Option<GstStatus>,
+    /// Only include records with one of these entity types, e.g. PRV
+    #[arg(long = "type")]
+    pub entity_type: Vec<EntityType>,
+    /// Only include records with an entity type in one of these categories, in any case, e.g. company
+    #[arg(long)]
+    pub category: Vec<EntityCategory>,
+    /// Only include records in one of these states, in any case, e.g. VIC
+    #[arg(long)]
+    pub state: Vec<String>,
+    /// Only include records with this postcode, or in a range like 3000-3999
+    #[arg(long)]
+    pub postcode: Option<PostcodeRange>,
+    /// Only include records whose ABN status started on or after this date
+    #[arg(long)]
+    pub status_since_from: Option<NaiveDate>,
+    /// Only include records whose ABN status started on or before this date
+    #[arg(long)]
+    pub status_since_to: Option<NaiveDate>,
+    /// Only include records last updated on or after this date
+    #[arg(long)]
+    pub last_updated_from: Option<NaiveDate>,
+    /// Only include records last updated on or before this date
+    #[arg(long)]
+    pub last_updated_to: Option<NaiveDate>,
+    /// Only include records with (true) or without (false) an ASIC number
+    #[arg(long)]
+    pub has_asic_number: Option<bool>,
+}
+
+impl Filter {
+    pub fn matches(&self, record: &ABNRecord) -> bool {
+        self.status.is_none_or(|x| record.status == x)
+            && self.gst_status.is_none_or(|x| record.gst_status == x.0)
+            && (self.entity_type.is_empty() || self.entity_type.contains(&record.entity_type))
+            && (self.category.is_empty() || self.category.contains(&record.entity_type.category()))
+            && (self.state.is_empty()
+                || record.state.as_ref().is_some_and(|x| {
+                    self.state
+                        .iter()
+                        .any(|state| state.trim().eq_ignore_ascii_case(x))
+                }))
+            && self.postcode.is_none_or(|x| {
+                record
+                    .postcode
+                    .as_ref()
+                    .and_then(|x| x.parse().ok())
+                    .is_some_and(|postcode| x.contains(postcode))
+            })
+            && self
+                .status_since_from
+                .is_none_or(|x| record.status_since >= x)
+            && self
+                .status_since_to
+                .is_none_or(|x| record.status_since <= x)
+            && self
+                .last_updated_from
+                .is_none_or(|x| record.last_updated >= x)
+            && self
+                .last_updated_to
+                .is_none_or(|x| record.last_updated <= x)
+            && self
+                .has_asic_number
+                .is_none_or(|x| record.asic_number.is_some() == x)
+    }
+}
+
+/// GST status to filter on, where none means never registered.
+#[derive(Debug, Clone, Copy)]
+pub struct GstStatus(pub Option<Status>);
+
+impl FromStr for GstStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.eq_ignore_ascii_case("none") {
+            return Ok(Self(None));
+        }
+        Ok(Self(Some(s.parse()?)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PostcodeRange {
+    pub from: u16,
+    pub to: u16,
+}
+
+impl PostcodeRange {
+    pub fn contains(self, postcode: u16) -> bool {
+        (self.from..=self.to).contains(&postcode)
+    }
+}
+
+impl FromStr for PostcodeRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (from, to) = s.split_once('-').unwrap_or((s, s));
+        let parse = |x: &str| {
+            x.trim()
+                .parse()
+                .map_err(|_| anyhow!("invalid postcode: {x}"))
+        };
+        let range = Self {
+            from: parse(from)?,
+            to: parse(to)?,
+        };
+        ensure!(range.from <= range.to, "postcode range is backwards");
+        Ok(range)
+    }
+}
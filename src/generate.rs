@@ -0,0 +1,505 @@
+//! Generates synthetic extracts in the ABR's XML layout, so tests and benchmarks don't need the
+//! real data. Output only depends on the options, including the seed.
+
+use std::{
+    fmt::Write as _,
+    io::{self, BufWriter, Write},
+    str::FromStr,
+};
+
+use anyhow::{ensure, Error, Result};
+use chrono::{Days, NaiveDate};
+use clap::Args;
+
+use crate::{model::EntityType, xml::escape};
+
+const GIVEN_NAMES: &[&str] = &[
+    "JOHN", "MARY", "DAVID", "SARAH", "MICHAEL", "JESSICA", "PETER", "EMMA", "JAMES", "OLIVIA",
+    "WEI", "PRIYA", "MOHAMMED", "LINH", "GIUSEPPE", "ANNA", "KYLIE", "BRUCE", "CHLOE", "TOM",
+];
+const FAMILY_NAMES: &[&str] = &[
+    "SMITH",
+    "JONES",
+    "WILLIAMS",
+    "BROWN",
+    "WILSON",
+    "TAYLOR",
+    "NGUYEN",
+    "JOHNSON",
+    "MARTIN",
+    "WHITE",
+    "O'BRIEN",
+    "MCDONALD",
+    "SINGH",
+    "CHEN",
+    "ROSSI",
+    "KELLY",
+    "SMITH-JONES",
+    "PATEL",
+    "MACKENZIE",
+    "LEE",
+];
+const TITLES: &[&str] = &["MR", "MRS", "MS", "MISS", "DR"];
+const WORDS: &[&str] = &[
+    "HARBOUR",
+    "OUTBACK",
+    "SOUTHERN",
+    "CROSS",
+    "COASTAL",
+    "BLUE",
+    "GUM",
+    "WATTLE",
+    "RED",
+    "CENTRE",
+    "SUMMIT",
+    "RIVERINA",
+    "BAY",
+    "HILLS",
+    "GOLDEN",
+    "PACIFIC",
+    "KOALA",
+    "EUCALYPT",
+    "NORTHSIDE",
+    "METRO",
+];
+const TRADES: &[&str] = &[
+    "PLUMBING",
+    "ELECTRICAL",
+    "CONSTRUCTIONS",
+    "CAFE",
+    "CONSULTING",
+    "TRANSPORT",
+    "CLEANING",
+    "PROPERTY",
+    "HOLDINGS",
+    "INVESTMENTS",
+    "LANDSCAPING",
+    "MEDICAL",
+    "IT SERVICES",
+    "BAKERY",
+    "AUTOMOTIVE",
+];
+const STATES: &[(&str, u16, u16)] = &[
+    ("NSW", 2000, 2999),
+    ("VIC", 3000, 3999),
+    ("QLD", 4000, 4999),
+    ("SA", 5000, 5799),
+    ("WA", 6000, 6797),
+    ("TAS", 7000, 7799),
+    ("NT", 800, 899),
+    ("ACT", 2600, 2618),
+];
+const DEFAULT_ENTITY_TYPES: &[(EntityType, u32)] = &[
+    (EntityType::IND, 40),
+    (EntityType::PRV, 25),
+    (EntityType::DIT, 7),
+    (EntityType::SMF, 6),
+    (EntityType::DTT, 4),
+    (EntityType::FPT, 4),
+    (EntityType::PTR, 3),
+    (EntityType::FUT, 2),
+    (EntityType::OIE, 2),
+    (EntityType::TRT, 2),
+    (EntityType::STR, 2),
+    (EntityType::UIE, 1),
+    (EntityType::PUB, 1),
+    (EntityType::SGE, 1),
+];
+
+const DEFAULT: GenerateOptions = GenerateOptions {
+    records: 1000,
+    seed: 0,
+    entity_types: None,
+    cancelled: 0.3,
+    gst: 0.5,
+    gst_cancelled: 0.2,
+    dgr: 0.02,
+    other_names: 0.8,
+    replaced: 0.0,
+    malformed: 0.0,
+};
+
+#[derive(Debug, Clone, Args)]
+pub struct GenerateOptions {
+    /// Number of records to generate
+    #[arg(long, default_value_t = DEFAULT.records)]
+    pub records: usize,
+    /// Seed for the generator, the same seed always gives the same extract
+    #[arg(long, default_value_t = DEFAULT.seed)]
+    pub seed: u64,
+    /// Relative weights of entity types, e.g. IND=4,PRV=3,DIT=1
+    #[arg(long)]
+    pub entity_types: Option<Weights>,
+    /// Share of ABNs that are cancelled
+    #[arg(long, default_value_t = DEFAULT.cancelled)]
+    pub cancelled: f64,
+    /// Share of records that have been registered for GST
+    #[arg(long, default_value_t = DEFAULT.gst)]
+    pub gst: f64,
+    /// Share of GST registrations that have since been cancelled
+    #[arg(long, default_value_t = DEFAULT.gst_cancelled)]
+    pub gst_cancelled: f64,
+    /// Share of non-individuals that are deductible gift recipients
+    #[arg(long, default_value_t = DEFAULT.dgr)]
+    pub dgr: f64,
+    /// Average number of business, trade and other names per record
+    #[arg(long, default_value_t = DEFAULT.other_names)]
+    pub other_names: f64,
+    /// Share of records flagged as replaced
+    #[arg(long, default_value_t = DEFAULT.replaced)]
+    pub replaced: f64,
+    /// Share of records that are corrupted in a way the parser should reject
+    #[arg(long, default_value_t = DEFAULT.malformed)]
+    pub malformed: f64,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        DEFAULT
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Weights(Vec<(EntityType, u32)>);
+
+impl FromStr for Weights {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let weights: Vec<_> = s
+            .split(',')
+            .map(|x| {
+                let (entity_type, weight) = x.split_once('=').unwrap_or((x, "1"));
+                Ok((entity_type.trim().parse()?, weight.trim().parse()?))
+            })
+            .collect::<Result<_>>()?;
+        ensure!(
+            weights.iter().any(|(_, x)| *x > 0),
+            "at least one entity type needs a weight"
+        );
+        Ok(Self(weights))
+    }
+}
+
+pub fn run(options: GenerateOptions) -> Result<()> {
+    let mut output = BufWriter::new(io::stdout().lock());
+    generate(&options, &mut output)?;
+    output.flush()?;
+
+    Ok(())
+}
+
+/// Writes a whole extract, with the same header and footer lines as the ABR's chunks.
+pub fn generate(options: &GenerateOptions, output: &mut impl Write) -> Result<()> {
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(output, "<Transfer>")?;
+    writeln!(output, "<extractTime>20241127</extractTime>")?;
+    writeln!(output, "<extractType>Full</extractType>")?;
+    for record in Generator::new(options.clone()).take(options.records) {
+        writeln!(output, "{record}")?;
+    }
+    writeln!(output, "</Transfer>")?;
+
+    Ok(())
+}
+
+/// An endless supply of single line `<ABR>` records.
+pub struct Generator {
+    options: GenerateOptions,
+    rng: Rng,
+}
+
+impl Generator {
+    pub fn new(options: GenerateOptions) -> Self {
+        let rng = Rng(options.seed);
+        Self { options, rng }
+    }
+
+    fn record(&mut self) -> String {
+        let options = &self.options;
+        let rng = &mut self.rng;
+
+        let entity_type = rng.weighted(
+            options
+                .entity_types
+                .as_ref()
+                .map_or(DEFAULT_ENTITY_TYPES, |x| &x.0),
+        );
+        let individual = entity_type == EntityType::IND;
+        let acn = matches!(entity_type, EntityType::PRV | EntityType::PUB).then(|| acn(rng));
+        let abn = match &acn {
+            Some(acn) => abn(acn),
+            None => abn(&format!("{:09}", rng.below(1_000_000_000))),
+        };
+
+        let status_since = rng.date(date(1999, 11, 1), date(2024, 11, 27));
+        let last_updated = rng.date(status_since, date(2024, 11, 27));
+        let replaced = if rng.chance(options.replaced) {
+            "Y"
+        } else {
+            "N"
+        };
+        let status = if rng.chance(options.cancelled) {
+            "CAN"
+        } else {
+            "ACT"
+        };
+
+        let mut x = String::new();
+        let _ = write!(
+            x,
+            r#"<ABR recordLastUpdatedDate="{}" replaced="{replaced}"><ABN status="{status}" ABNStatusFromDate="{}">{abn}</ABN>"#,
+            last_updated.format("%Y%m%d"),
+            status_since.format("%Y%m%d"),
+        );
+        let _ = write!(
+            x,
+            "<EntityType><EntityTypeInd>{entity_type:?}</EntityTypeInd><EntityTypeText>{}</EntityTypeText></EntityType>",
+            escape(entity_type.description()),
+        );
+
+        let family = *rng.pick(FAMILY_NAMES);
+        if individual {
+            x.push_str(r#"<LegalEntity><IndividualName type="LGL">"#);
+            if rng.chance(0.3) {
+                let _ = write!(x, "<NameTitle>{}</NameTitle>", rng.pick(TITLES));
+            }
+            let _ = write!(x, "<GivenName>{}</GivenName>", rng.pick(GIVEN_NAMES));
+            if rng.chance(0.5) {
+                let _ = write!(x, "<GivenName>{}</GivenName>", rng.pick(GIVEN_NAMES));
+            }
+            let _ = write!(
+                x,
+                "<FamilyName>{}</FamilyName></IndividualName>",
+                escape(family)
+            );
+            address(&mut x, rng);
+            x.push_str("</LegalEntity>");
+        } else {
+            let name = entity_name(rng, entity_type, family);
+            let _ = write!(
+                x,
+                r#"<MainEntity><NonIndividualName type="MN"><NonIndividualNameText>{}</NonIndividualNameText></NonIndividualName>"#,
+                escape(&name),
+            );
+            address(&mut x, rng);
+            x.push_str("</MainEntity>");
+        }
+
+        if let Some(acn) = &acn {
+            let _ = write!(
+                x,
+                r#"<ASICNumber ASICNumberType="undetermined">{acn}</ASICNumber>"#
+            );
+        }
+
+        if rng.chance(options.gst) {
+            let since = rng.date(date(2000, 7, 1), date(2024, 11, 27));
+            let status = if rng.chance(options.gst_cancelled) {
+                "CAN"
+            } else {
+                "ACT"
+            };
+            let _ = write!(
+                x,
+                r#"<GST status="{status}" GSTStatusFromDate="{}" />"#,
+                since.format("%Y%m%d")
+            );
+        } else {
+            x.push_str(r#"<GST status="NON" GSTStatusFromDate="19000101" />"#);
+        }
+
+        if !individual && rng.chance(options.dgr) {
+            for _ in 0..1 + rng.below(2) {
+                let since = rng.date(date(2000, 1, 1), date(2024, 11, 27));
+                let _ = write!(
+                    x,
+                    r#"<DGR status="ACT" DGRStatusFromDate="{}">"#,
+                    since.format("%Y%m%d")
+                );
+                if rng.chance(0.7) {
+                    let _ = write!(
+                        x,
+                        r#"<NonIndividualName type="DGR"><NonIndividualNameText>{} {} FUND</NonIndividualNameText></NonIndividualName>"#,
+                        rng.pick(WORDS),
+                        rng.pick(&["PUBLIC", "BUILDING", "SCHOLARSHIP", "RELIEF"]),
+                    );
+                }
+                x.push_str("</DGR>");
+            }
+        }
+
+        // geometric, so most records have none or one and a few have many
+        let p = options.other_names / (1.0 + options.other_names);
+        while rng.chance(p) {
+            let kind = *rng.pick(&["BN", "BN", "TRD", "TRD", "OTN"]);
+            let name = format!("{} {}", rng.pick(WORDS), rng.pick(TRADES));
+            let _ = write!(
+                x,
+                r#"<OtherEntity><NonIndividualName type="{kind}"><NonIndividualNameText>{}</NonIndividualNameText></NonIndividualName></OtherEntity>"#,
+                escape(&name),
+            );
+        }
+
+        x.push_str("</ABR>");
+
+        if rng.chance(options.malformed) {
+            malform(&mut x, rng);
+        }
+        x
+    }
+}
+
+impl Iterator for Generator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        Some(self.record())
+    }
+}
+
+fn entity_name(rng: &mut Rng, entity_type: EntityType, family: &str) -> String {
+    let word = rng.pick(WORDS);
+    let trade = rng.pick(TRADES);
+    match entity_type {
+        EntityType::PRV => format!("{word} {trade} PTY LTD"),
+        EntityType::PUB => format!("{word} {trade} LIMITED"),
+        EntityType::DIT | EntityType::DTT | EntityType::TRT => {
+            format!("THE TRUSTEE FOR THE {family} FAMILY TRUST")
+        }
+        EntityType::FUT => format!("THE TRUSTEE FOR {word} {trade} UNIT TRUST"),
+        EntityType::SMF => format!("THE TRUSTEE FOR {family} SUPERANNUATION FUND"),
+        EntityType::FPT | EntityType::PTR => {
+            format!(
+                "{} & {} {family}",
+                rng.pick(GIVEN_NAMES),
+                rng.pick(GIVEN_NAMES)
+            )
+        }
+        EntityType::STR => format!("THE OWNERS - STRATA PLAN NO {}", 1000 + rng.below(90000)),
+        EntityType::SGE => format!("DEPARTMENT OF {word} {trade}"),
+        _ => format!("{word} {trade} ASSOCIATION INCORPORATED"),
+    }
+}
+
+fn address(x: &mut String, rng: &mut Rng) {
+    let (state, from, to) = *rng.pick(STATES);
+    let postcode = from + rng.below(u64::from(to - from) + 1) as u16;
+    match rng.below(20) {
+        // no address at all
+        0 => (),
+        // the extract has some postcodes without a state
+        1 => {
+            let _ = write!(
+                x,
+                "<BusinessAddress><AddressDetails><Postcode>{postcode:04}</Postcode></AddressDetails></BusinessAddress>"
+            );
+        }
+        2 => {
+            let _ = write!(
+                x,
+                "<BusinessAddress><AddressDetails><State>{state}</State><Postcode>0000</Postcode></AddressDetails></BusinessAddress>"
+            );
+        }
+        _ => {
+            let _ = write!(
+                x,
+                "<BusinessAddress><AddressDetails><State>{state}</State><Postcode>{postcode:04}</Postcode></AddressDetails></BusinessAddress>"
+            );
+        }
+    }
+}
+
+fn malform(x: &mut String, rng: &mut Rng) {
+    match rng.below(6) {
+        0 => {
+            let len = rng.below(x.len() as u64) as usize;
+            x.truncate(len);
+        }
+        1 => *x = x.replacen("<EntityTypeInd>", "<EntityTypeInd>X", 1),
+        2 => *x = x.replacen(r#"ABNStatusFromDate=""#, r#"ABNStatusFromDate="2024"#, 1),
+        3 => {
+            *x = x.replacen(r#"type="MN""#, r#"type="ZZ""#, 1).replacen(
+                r#"type="LGL""#,
+                r#"type="ZZ""#,
+                1,
+            )
+        }
+        4 => *x = x.replacen("</ABN>", "</ABN></EntityType>", 1),
+        _ => *x = x.replacen(r#"<GST status=""#, r#"<GST status="X"#, 1),
+    }
+}
+
+/// A random nine digit ACN with a valid check digit.
+fn acn(rng: &mut Rng) -> String {
+    let digits = format!("{:08}", rng.below(100_000_000));
+    let sum: u32 = digits
+        .bytes()
+        .zip((1..=8).rev())
+        .map(|(d, w)| u32::from(d - b'0') * w)
+        .sum();
+    format!("{digits}{}", (10 - sum % 10) % 10)
+}
+
+/// Prefixes nine digits with the two digits that make a valid ABN, like the ABR does for ACNs.
+fn abn(digits: &str) -> String {
+    const WEIGHTS: [u32; 11] = [10, 1, 3, 5, 7, 9, 11, 13, 15, 17, 19];
+    (10..=99)
+        .map(|prefix| format!("{prefix}{digits}"))
+        .find(|abn| {
+            let sum: u32 = abn
+                .bytes()
+                .zip(WEIGHTS)
+                .enumerate()
+                .map(|(i, (d, w))| (u32::from(d - b'0') - u32::from(i == 0)) * w)
+                .sum();
+            sum.is_multiple_of(89)
+        })
+        .expect("every nine digits have an abn prefix")
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+/// splitmix64, which is plenty for fixtures and keeps extracts stable across versions.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    fn pick<'a, T>(&mut self, xs: &'a [T]) -> &'a T {
+        &xs[self.below(xs.len() as u64) as usize]
+    }
+
+    fn weighted<T: Copy>(&mut self, xs: &[(T, u32)]) -> T {
+        let total: u64 = xs.iter().map(|(_, w)| u64::from(*w)).sum();
+        let mut n = self.below(total);
+        for (x, w) in xs {
+            if n < u64::from(*w) {
+                return *x;
+            }
+            n -= u64::from(*w);
+        }
+        unreachable!("weights always add up")
+    }
+
+    fn date(&mut self, from: NaiveDate, to: NaiveDate) -> NaiveDate {
+        let days = (to - from).num_days().max(0) as u64;
+        from + Days::new(self.below(days + 1))
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    ffi::OsString,
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use clap::Args;
+use serde::Serialize;
+
+use crate::{
+    filter::Filter,
+    model::{ABNRecord, EntityName, OtherNameKind, Status},
+    normalise::tokens,
+};
+
+const MAGIC: &[u8; 8] = b"SABNIDX3";
+// query tokens shorter than this only match whole tokens, as shorter prefixes match most records
+const MIN_PREFIX: usize = 3;
+// records that pass the filter to score for a search, the ones ranked highest by their postings
+const MAX_CANDIDATES: usize = 2_000;
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    /// Converted dataset that has been indexed with the index command
+    data: PathBuf,
+    /// Names or name prefixes to search for
+    #[arg(required = true)]
+    query: Vec<String>,
+    /// Maximum number of results to print
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+    #[command(flatten)]
+    filter: Filter,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hit {
+    pub score: f64,
+    #[serde(flatten)]
+    pub record: ABNRecord,
+}
+
+/// Inverted index from name tokens to records, stored as byte offsets into the dataset.
+/// ABNs and ACNs are kept alongside for direct lookups.
+pub struct Index {
+    offsets: Vec<u64>,
+    terms: Vec<(String, Vec<u32>)>,
+    abns: Vec<(u64, u32)>,
+    acns: Vec<(u64, u32)>,
+}
+
+impl Index {
+    pub fn build(data: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(data)?);
+        let mut offsets = Vec::new();
+        let mut postings: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut abns = Vec::new();
+        let mut acns = Vec::new();
+
+        let mut line = String::new();
+        let mut offset = 0;
+        loop {
+            line.clear();
+            let n = reader.read_line(&mut line)?;
+            if n == 0 {
+                break;
+            }
+
+            let record: ABNRecord = serde_json::from_str(&line)
+                .with_context(|| format!("invalid record at byte {offset}"))?;
+            let id = u32::try_from(offsets.len()).context("too many records")?;
+            offsets.push(offset);
+            offset += n as u64;
+
+            let abn = record.abn.parse().context("invalid abn")?;
+            abns.push((abn, id));
+            if let Some(acn) = record.asic_number.as_ref().and_then(|x| x.parse().ok()) {
+                acns.push((acn, id));
+            }
+
+            let mut tokens: Vec<_> = names(&record)
+                .into_iter()
+                .flat_map(|(_, name)| tokens(name))
+                .collect();
+            tokens.sort_unstable();
+            tokens.dedup();
+            for token in tokens {
+                postings.entry(token).or_default().push(id);
+            }
+        }
+
+        let mut terms: Vec<_> = postings.into_iter().collect();
+        terms.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        abns.sort_unstable();
+        acns.sort_unstable();
+
+        Ok(Self {
+            offsets,
+            terms,
+            abns,
+            acns,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+        let mut reader = Reader(&bytes);
+        let magic = reader.take(MAGIC.len())?;
+        ensure!(magic.starts_with(b"SABNIDX"), "not a search index");
+        ensure!(
+            magic == MAGIC,
+            "search index was built by another version, rebuild it with the index command"
+        );
+
+        let offsets = (0..reader.u64()?)
+            .map(|_| reader.u64())
+            .collect::<Result<_>>()?;
+        let terms = (0..reader.u64()?)
+            .map(|_| {
+                let len = reader.u32()? as usize;
+                let term = String::from_utf8(reader.take(len)?.to_vec())?;
+                let ids = (0..reader.u32()?)
+                    .map(|_| reader.u32())
+                    .collect::<Result<_>>()?;
+                Ok((term, ids))
+            })
+            .collect::<Result<_>>()?;
+        let abns = reader.numbers()?;
+        let acns = reader.numbers()?;
+
+        Ok(Self {
+            offsets,
+            terms,
+            abns,
+            acns,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MAGIC)?;
+        w.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+        for offset in &self.offsets {
+            w.write_all(&offset.to_le_bytes())?;
+        }
+        w.write_all(&(self.terms.len() as u64).to_le_bytes())?;
+        for (term, ids) in &self.terms {
+            w.write_all(&(term.len() as u32).to_le_bytes())?;
+            w.write_all(term.as_bytes())?;
+            w.write_all(&(ids.len() as u32).to_le_bytes())?;
+            for id in ids {
+                w.write_all(&id.to_le_bytes())?;
+            }
+        }
+        for numbers in [&self.abns, &self.acns] {
+            w.write_all(&(numbers.len() as u64).to_le_bytes())?;
+            for (number, id) in numbers {
+                w.write_all(&number.to_le_bytes())?;
+                w.write_all(&id.to_le_bytes())?;
+            }
+        }
+        w.flush()?;
+
+        Ok(())
+    }
+
+    pub fn record_count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn offset(&self, id: u32) -> u64 {
+        self.offsets[id as usize]
+    }
+
+    /// Record id with this ABN, ignoring any spaces.
+    pub fn abn(&self, abn: &str) -> Option<u32> {
+        lookup_number(&self.abns, abn).first().copied()
+    }
+
+    /// Record ids with this ACN, ignoring any spaces.
+    pub fn acn(&self, acn: &str) -> Vec<u32> {
+        lookup_number(&self.acns, acn)
+    }
+
+    /// Record ids containing exactly `token`, sorted.
+    pub fn exact(&self, token: &str) -> &[u32] {
+        match self.terms.binary_search_by(|(t, _)| t.as_str().cmp(token)) {
+            Ok(i) => &self.terms[i].1,
+            Err(_) => &[],
+        }
+    }
+
+    /// Record ids containing a token that starts with the longest prefix of `token` that any
+    /// indexed token starts with, at least `MIN_PREFIX` characters long, and how many characters
+    /// that is. For finding words that are misspelt after their first few letters.
+    pub fn nearest_prefix(&self, token: &str) -> Option<(usize, Vec<u32>)> {
+        let ends: Vec<_> = token
+            .char_indices()
+            .map(|(i, c)| i + c.len_utf8())
+            .collect();
+        (MIN_PREFIX..=ends.len()).rev().find_map(|len| {
+            let prefix = &token[..ends[len - 1]];
+            let start = self.terms.partition_point(|(t, _)| t.as_str() < prefix);
+            let found = self.terms.get(start)?.0.starts_with(prefix);
+            found.then(|| (len, self.lookup(prefix)))
+        })
+    }
+
+    /// Record ids containing a token that starts with `prefix`, sorted.
+    fn lookup(&self, prefix: &str) -> Vec<u32> {
+        let start = self.terms.partition_point(|(t, _)| t.as_str() < prefix);
+        let mut ids: Vec<_> = self.terms[start..]
+            .iter()
+            .take_while(|(t, _)| t.starts_with(prefix))
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Records with every token of `query` as a whole token or prefix, best match first. Records
+    /// are read best matching tokens first, stopping once `MAX_CANDIDATES` pass the filter.
+    pub fn search(
+        &self,
+        dataset: &mut Dataset,
+        query: &str,
+        filter: &Filter,
+        limit: usize,
+    ) -> Result<Vec<Hit>> {
+        let mut tokens: Vec<_> = tokens(query)
+            .into_iter()
+            .map(|t| {
+                let ids = match t.chars().count() {
+                    n if n < MIN_PREFIX => self.exact(&t).to_vec(),
+                    _ => self.lookup(&t),
+                };
+                (t, ids)
+            })
+            .collect();
+        if tokens.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // rarer tokens say more about a match
+        let total = self.offsets.len() as f64;
+        let weights: Vec<_> = tokens
+            .iter()
+            .map(|(t, ids)| (t.clone(), (1.0 + total / (1.0 + ids.len() as f64)).ln()))
+            .collect();
+
+        tokens.sort_by_key(|(_, ids)| ids.len());
+        let mut candidates = tokens[0].1.clone();
+        for (_, ids) in &tokens[1..] {
+            candidates.retain(|id| ids.binary_search(id).is_ok());
+        }
+
+        // broad queries are ranked by their postings first, so only the best need to be read
+        if candidates.len() > MAX_CANDIDATES {
+            let mut ranked: Vec<_> = candidates
+                .into_iter()
+                .map(|id| {
+                    let score: f64 = weights
+                        .iter()
+                        .map(|(t, idf)| match self.exact(t).binary_search(&id) {
+                            Ok(_) => *idf,
+                            Err(_) => idf / 2.0,
+                        })
+                        .sum();
+                    (id, score)
+                })
+                .collect();
+            ranked.sort_by(|a, b| {
+                b.1.partial_cmp(&a.1)
+                    .unwrap_or(Ordering::Equal)
+                    .then(a.0.cmp(&b.0))
+            });
+            candidates = ranked.into_iter().map(|(id, _)| id).collect();
+        }
+
+        // filtering as they're read, so records the filter rejects don't use up the candidates
+        let mut hits = Vec::new();
+        for id in candidates {
+            if hits.len() == MAX_CANDIDATES {
+                break;
+            }
+            let record = dataset.get(self.offsets[id as usize])?;
+            if filter.matches(&record) {
+                hits.push(Hit {
+                    score: score(&record, &weights),
+                    record,
+                });
+            }
+        }
+
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| {
+                    (a.record.status != Status::Active).cmp(&(b.record.status != Status::Active))
+                })
+                .then_with(|| a.record.abn.cmp(&b.record.abn))
+        });
+        hits.truncate(limit);
+
+        Ok(hits)
+    }
+}
+
+/// Random access to the records of a converted dataset.
+pub struct Dataset {
+    reader: BufReader<File>,
+    line: String,
+}
+
+impl Dataset {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(File::open(path)?),
+            line: String::new(),
+        })
+    }
+
+    pub fn get(&mut self, offset: u64) -> Result<ABNRecord> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.line.clear();
+        self.reader.read_line(&mut self.line)?;
+        serde_json::from_str(&self.line)
+            .with_context(|| format!("invalid record at byte {offset}, is the index stale?"))
+    }
+}
+
+pub fn index_path(data: &Path) -> PathBuf {
+    let mut path = OsString::from(data);
+    path.push(".idx");
+    path.into()
+}
+
+pub fn build(data: &Path) -> Result<()> {
+    let index = Index::build(data)?;
+    let path = index_path(data);
+    index.save(&path)?;
+    eprintln!(
+        "indexed {} records and {} terms into {path:?}",
+        index.offsets.len(),
+        index.terms.len()
+    );
+
+    Ok(())
+}
+
+pub fn search(args: SearchArgs) -> Result<()> {
+    let index = Index::load(&index_path(&args.data))?;
+    let mut dataset = Dataset::open(&args.data)?;
+    let hits = index.search(
+        &mut dataset,
+        &args.query.join(" "),
+        &args.filter,
+        args.limit,
+    )?;
+    for hit in hits {
+        println!("{}", serde_json::to_string(&hit)?);
+    }
+
+    Ok(())
+}
+
+/// Names worth searching on a record, with how much a match on each is worth.
+pub fn names(record: &ABNRecord) -> Vec<(f64, &str)> {
+    let mut names = match &record.entity_name {
+        EntityName::Individual {
+            title: _,
+            given,
+            given_2,
+            family,
+        } => [given.as_deref(), given_2.as_deref(), Some(family.as_str())]
+            .into_iter()
+            .flatten()
+            .map(|x| (1.0, x))
+            .collect(),
+        // trusts are searched by their own name rather than the trustee boilerplate
+        EntityName::NonIndividual { name } => match &record.trust {
+            Some(trust) => vec![(1.0, trust.name.as_str())],
+            None => vec![(1.0, name.as_str())],
+        },
+        EntityName::Redacted { .. } => vec![],
+    };
+    names.extend(record.other_names.iter().map(|x| match x.kind {
+        OtherNameKind::BN => (0.8, x.name.as_str()),
+        _ => (0.6, x.name.as_str()),
+    }));
+    names
+}
+
+/// Scores the best matching name, preferring exact tokens over prefixes and
+/// names without much else in them.
+fn score(record: &ABNRecord, query: &[(String, f64)]) -> f64 {
+    names(record)
+        .into_iter()
+        .map(|(weight, name)| {
+            let tokens = tokens(name);
+            let mut matched = 0;
+            let mut total = 0.0;
+            for (q, idf) in query {
+                let best = tokens
+                    .iter()
+                    .map(|t| match t.starts_with(q.as_str()) {
+                        true if t == q => 1.0,
+                        true => 0.5,
+                        false => 0.0,
+                    })
+                    .fold(0.0, f64::max);
+                if best > 0.0 {
+                    matched += 1;
+                }
+                total += best * idf;
+            }
+            let extra = tokens.len().saturating_sub(matched) as f64;
+            weight * total / (1.0 + 0.1 * extra)
+        })
+        .fold(0.0, f64::max)
+}
+
+fn lookup_number(numbers: &[(u64, u32)], number: &str) -> Vec<u32> {
+    let Ok(number) = number.replace(' ', "").parse::<u64>() else {
+        return Vec::new();
+    };
+    let start = numbers.partition_point(|(x, _)| *x < number);
+    numbers[start..]
+        .iter()
+        .take_while(|(x, _)| *x == number)
+        .map(|(_, id)| *id)
+        .collect()
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            bail!("truncated search index");
+        }
+        let (x, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(x)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn numbers(&mut self) -> Result<Vec<(u64, u32)>> {
+        (0..self.u64()?)
+            .map(|_| Ok((self.u64()?, self.u32()?)))
+            .collect()
+    }
+}
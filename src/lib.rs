@@ -0,0 +1,20 @@
+pub mod arrow;
+pub mod checkpoint;
+pub mod convert;
+pub mod emit;
+pub mod filter;
+pub mod generate;
+pub mod index;
+pub mod manifest;
+pub mod matching;
+pub mod model;
+pub mod normalise;
+pub mod output;
+pub mod parser;
+pub mod postgres;
+pub mod privacy;
+pub mod progress;
+pub mod projection;
+pub mod schema;
+pub mod serve;
+pub mod xml;
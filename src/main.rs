@@ -1,38 +1,48 @@
-use itertools::Itertools;
-use rayon::prelude::*;
-use std::io::BufReader;
-use std::{fs::File, io::BufRead};
+use std::path::PathBuf;
 
 use anyhow::Result;
+use clap::{Parser, Subcommand};
+use simple_abns::{convert, emit, generate, index, matching, schema, serve};
 
-mod model;
-mod parser;
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Convert the raw XML chunks in ./raw and print each record as a JSON line
+    Convert(convert::ConvertArgs),
+    /// Build a name search index next to a converted dataset
+    Index {
+        /// Uncompressed JSON lines output of convert
+        data: PathBuf,
+    },
+    /// Search a converted dataset by entity, business or trade name
+    Search(index::SearchArgs),
+    /// Find the ABNs that best match a CSV of free-text names
+    Match(matching::MatchArgs),
+    /// Serve lookups and searches of an indexed dataset over HTTP
+    Serve(serve::ServeArgs),
+    /// Generate a synthetic extract in the ABR's XML layout
+    Gen(generate::GenerateOptions),
+    /// Convert JSON lines back into an extract in the ABR's XML layout
+    Xml(emit::XmlArgs),
+    /// Print the JSON Schema of the records convert writes
+    Schema,
+}
 
 fn main() -> Result<()> {
-    for i in 1..=20 {
-        let path = format!("raw/20241127_Public{i:02}.xml");
-        eprintln!("{path}");
-        let file = BufReader::new(File::open(path)?);
-        for chunk in &file.lines().enumerate().chunks(65535) {
-            let mut todo = Vec::new();
-            for (i, line) in chunk {
-                let line = line?;
-                if !(i < 4 || line == "</Transfer>") {
-                    todo.push(line);
-                }
-            }
-            let chunk: Vec<_> = todo
-                .par_iter()
-                .map(|line| parser::parse_record(line))
-                .collect();
-            for record in chunk {
-                match record {
-                    Ok(x) => println!("{}", serde_json::to_string(&x)?),
-                    Err(e) => eprintln!("{e}"),
-                };
-            }
-        }
+    match Cli::parse().command {
+        Command::Convert(args) => convert::run(args),
+        Command::Index { data } => index::build(&data),
+        Command::Search(args) => index::search(args),
+        Command::Match(args) => matching::run(args),
+        Command::Serve(args) => serve::run(args),
+        Command::Gen(options) => generate::run(options),
+        Command::Xml(args) => emit::run(args),
+        Command::Schema => schema::print(),
     }
-
-    Ok(())
 }
@@ -0,0 +1,108 @@
+//! A record of what went into a conversion and what came out, so consumers of a published
+//! dataset can check they've loaded all of it, unchanged, and know how it was made.
+
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{model::SCHEMA_VERSION, output::Written};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub simple_abns_version: String,
+    /// The `SCHEMA_VERSION` of the records written
+    pub schema_version: u32,
+    /// The date of the ABR's extract, from the input files' names
+    pub extract_date: Option<NaiveDate>,
+    /// The command line the dataset was made with
+    pub arguments: Vec<String>,
+    pub inputs: Vec<InputFile>,
+    pub outputs: Vec<OutputFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputFile {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputFile {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub sha256: String,
+    pub records: u64,
+}
+
+impl Manifest {
+    /// Hashes the inputs and finished outputs of a conversion. Inputs are read again rather than
+    /// hashed as they're converted, so resumed conversions get complete hashes too.
+    pub fn new(
+        inputs: &[impl AsRef<Path> + Sync],
+        outputs: &[(PathBuf, Written)],
+        arguments: Vec<String>,
+    ) -> Result<Self> {
+        let inputs = inputs
+            .par_iter()
+            .map(|path| {
+                let path = path.as_ref();
+                let (bytes, sha256) = sha256(path)?;
+                Ok(InputFile {
+                    path: path.into(),
+                    bytes,
+                    sha256,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let outputs = outputs
+            .par_iter()
+            .map(|(path, written)| {
+                let (bytes, sha256) = sha256(path)?;
+                Ok(OutputFile {
+                    path: path.clone(),
+                    bytes,
+                    sha256,
+                    records: written.records,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            simple_abns_version: env!("CARGO_PKG_VERSION").into(),
+            schema_version: SCHEMA_VERSION,
+            extract_date: inputs.first().and_then(|x| extract_date(&x.path)),
+            arguments,
+            inputs,
+            outputs,
+        })
+    }
+}
+
+/// The date an extract was published, from the start of its files' names, e.g.
+/// `20241127_Public01.xml`.
+pub fn extract_date(path: &Path) -> Option<NaiveDate> {
+    let name = path.file_name()?.to_str()?;
+    NaiveDate::parse_from_str(name.get(..8)?, "%Y%m%d").ok()
+}
+
+/// The size and hex SHA-256 of a file.
+pub fn sha256(path: &Path) -> Result<(u64, String)> {
+    let mut file = File::open(path).with_context(|| format!("failed to open {path:?}"))?;
+    let mut hasher = Sha256::new();
+    let bytes = io::copy(&mut file, &mut hasher)?;
+    let hash = hasher
+        .finalize()
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect();
+    Ok((bytes, hash))
+}
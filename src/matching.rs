@@ -0,0 +1,206 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    io,
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    index::{self, Dataset, Index},
+    model::{ABNRecord, Status},
+    normalise::core_key,
+};
+
+// tokens this common are skipped when gathering candidates, unless nothing rarer is left
+const MAX_POSTINGS: usize = 200_000;
+// candidates sharing the most tokens with a name that get a full comparison
+const MAX_CANDIDATES: usize = 2_000;
+
+#[derive(Debug, Args)]
+pub struct MatchArgs {
+    /// Converted dataset that has been indexed with the index command
+    data: PathBuf,
+    /// CSV with a name column, and optionally postcode and state columns
+    names: PathBuf,
+    /// Number of candidates to print for each name
+    #[arg(long, default_value_t = 3)]
+    candidates: usize,
+}
+
+/// A name to match, with where the business is if that's known.
+#[derive(Debug, Default, Deserialize)]
+pub struct Row {
+    pub name: String,
+    #[serde(default)]
+    pub postcode: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Candidate<'a> {
+    row: usize,
+    name: &'a str,
+    rank: usize,
+    abn: String,
+    matched_name: String,
+    score: f64,
+    status: Status,
+    postcode: Option<String>,
+    state: Option<String>,
+}
+
+pub struct Scored {
+    pub score: f64,
+    /// 2 if the postcode matched, 1 if only the state did, 0 otherwise
+    pub location: u8,
+    pub matched_name: String,
+    pub record: ABNRecord,
+}
+
+pub fn run(args: MatchArgs) -> Result<()> {
+    let index = Index::load(&index::index_path(&args.data))?;
+    let mut dataset = Dataset::open(&args.data)?;
+
+    let mut reader = csv::Reader::from_path(&args.names)
+        .with_context(|| format!("failed to read {:?}", args.names))?;
+    let mut writer = csv::Writer::from_writer(io::stdout().lock());
+    for (i, row) in reader.deserialize().enumerate() {
+        let row: Row = row.with_context(|| format!("invalid row {}", i + 1))?;
+        let matches = find(&index, &mut dataset, &row, args.candidates)?;
+        for (rank, x) in matches.into_iter().enumerate() {
+            writer.serialize(Candidate {
+                row: i + 1,
+                name: &row.name,
+                rank: rank + 1,
+                abn: x.record.abn,
+                matched_name: x.matched_name,
+                score: (x.score * 1000.0).round() / 1000.0,
+                status: x.record.status,
+                postcode: x.record.postcode,
+                state: x.record.state,
+            })?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// The `n` records with names most similar to the row's, best first, with ties broken by
+/// location and then active ABNs.
+pub fn find(index: &Index, dataset: &mut Dataset, row: &Row, n: usize) -> Result<Vec<Scored>> {
+    let name = core_key(&row.name);
+    let trigrams = trigrams(&name);
+
+    let mut tokens: Vec<_> = name.split(' ').filter(|x| !x.is_empty()).collect();
+    tokens.sort_unstable();
+    tokens.dedup();
+    tokens.sort_by_key(|t| index.exact(t).len());
+
+    // cheap first pass: rank records by the rarity of the tokens they share
+    let total = index.record_count() as f64;
+    let mut shared: HashMap<u32, f64> = HashMap::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let ids = index.exact(token);
+        if i > 0 && ids.len() > MAX_POSTINGS {
+            break;
+        }
+        let idf = (1.0 + total / (1.0 + ids.len() as f64)).ln();
+        for &id in ids {
+            *shared.entry(id).or_default() += idf;
+        }
+    }
+    // words that aren't indexed are likely misspelt, so look for words starting the same way,
+    // counting for less the less of the word they share
+    for token in tokens.iter().filter(|x| index.exact(x).is_empty()) {
+        let Some((len, ids)) = index.nearest_prefix(token) else {
+            continue;
+        };
+        if ids.len() > MAX_POSTINGS {
+            continue;
+        }
+        let share = len as f64 / token.chars().count() as f64;
+        let idf = share * (1.0 + total / (1.0 + ids.len() as f64)).ln();
+        for id in ids {
+            *shared.entry(id).or_default() += idf;
+        }
+    }
+    let mut shared: Vec<_> = shared.into_iter().collect();
+    // ties are broken by id, so the same candidates are kept on every run
+    shared.sort_unstable_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+    shared.truncate(MAX_CANDIDATES);
+
+    let mut scored = Vec::new();
+    for (id, _) in shared {
+        let record = dataset.get(index.offset(id))?;
+        let Some((score, matched_name)) = full_names(&record)
+            .into_iter()
+            .map(|x| (similarity(&trigrams, &self::trigrams(&core_key(&x))), x))
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+        else {
+            continue;
+        };
+        let same = |a: &Option<String>, b: &Option<String>| match (a, b) {
+            (Some(a), Some(b)) => !a.trim().is_empty() && a.trim().eq_ignore_ascii_case(b.trim()),
+            _ => false,
+        };
+        let location = if same(&row.postcode, &record.postcode) {
+            2
+        } else if same(&row.state, &record.state) {
+            1
+        } else {
+            0
+        };
+        scored.push(Scored {
+            score,
+            location,
+            matched_name,
+            record,
+        });
+    }
+
+    scored.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| b.location.cmp(&a.location))
+            .then_with(|| {
+                (a.record.status != Status::Active).cmp(&(b.record.status != Status::Active))
+            })
+            .then_with(|| a.record.abn.cmp(&b.record.abn))
+    });
+    scored.truncate(n);
+
+    Ok(scored)
+}
+
+/// Every name a record is known by, with individuals' names in full.
+fn full_names(record: &ABNRecord) -> Vec<String> {
+    let mut names = vec![record.entity_name.full_name()];
+    names.extend(record.other_names.iter().map(|x| x.name.clone()));
+    names.retain(|x| !x.is_empty());
+    names
+}
+
+/// Every three characters of a name, with spaces around it so starts and ends count too.
+pub fn trigrams(name: &str) -> HashSet<[char; 3]> {
+    let chars: Vec<_> = format!("  {name} ").chars().collect();
+    chars.windows(3).map(|x| [x[0], x[1], x[2]]).collect()
+}
+
+/// Dice coefficient of two trigram sets, from 0 to 1.
+pub fn similarity(a: &HashSet<[char; 3]>, b: &HashSet<[char; 3]>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(b).count() as f64 / (a.len() + b.len()) as f64
+}
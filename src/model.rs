@@ -3,7 +3,11 @@ use std::{error::Error, fmt, str::FromStr};
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Version of the JSON written for an `ABNRecord`, bumped whenever fields are added, removed or
+/// change meaning.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ABNRecord {
     pub abn: String,
     pub status: Status,
@@ -12,11 +16,11 @@ pub struct ABNRecord {
 
     pub entity_name: EntityName,
     pub entity_type: EntityType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust: Option<Trust>,
 
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub business_names: Vec<String>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub trade_names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_names: Vec<OtherName>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub postcode: Option<String>,
@@ -32,13 +36,156 @@ pub struct ABNRecord {
     pub gst_status_since: Option<NaiveDate>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl ABNRecord {
+    /// Registered business names, which used to be output as `business_names`.
+    pub fn business_names(&self) -> impl Iterator<Item = &str> {
+        self.other_names
+            .iter()
+            .filter(|x| x.kind == OtherNameKind::BN)
+            .map(|x| x.name.as_str())
+    }
+
+    /// Current and old trading names, which used to be output together as `trade_names`.
+    pub fn trade_names(&self) -> impl Iterator<Item = &str> {
+        self.other_names
+            .iter()
+            .filter(|x| matches!(x.kind, OtherNameKind::TRD | OtherNameKind::OTN))
+            .map(|x| x.name.as_str())
+    }
+}
+
+/// The trust behind a trust's or super fund's record, which is usually registered in the name of
+/// its trustee, e.g. "THE TRUSTEE FOR THE SMITH FAMILY TRUST".
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Trust {
+    /// The trust's own name, e.g. "THE SMITH FAMILY TRUST"
+    pub name: String,
+    pub kind: TrustKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustKind {
+    Discretionary,
+    Unit,
+    Fixed,
+    Hybrid,
+    SuperFund,
+    Other,
+}
+
+impl TrustKind {
+    /// Every kind of trust, in the order they're declared.
+    pub const ALL: [Self; 6] = [
+        Self::Discretionary,
+        Self::Unit,
+        Self::Fixed,
+        Self::Hybrid,
+        Self::SuperFund,
+        Self::Other,
+    ];
+
+    /// The kind as it's written, e.g. `super_fund`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Discretionary => "discretionary",
+            Self::Unit => "unit",
+            Self::Fixed => "fixed",
+            Self::Hybrid => "hybrid",
+            Self::SuperFund => "super_fund",
+            Self::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OtherName {
+    pub name: String,
+    pub kind: OtherNameKind,
+}
+
+/// The `type` of an `OtherEntity` name, written as the ABR's code.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum OtherNameKind {
+    BN,  // Business name
+    TRD, // Trading name
+    OTN, // Old trading name, linked to records from before 2012
+    Other(String),
+}
+
+impl From<String> for OtherNameKind {
+    fn from(s: String) -> Self {
+        match &*s {
+            "BN" => Self::BN,
+            "TRD" => Self::TRD,
+            "OTN" => Self::OTN,
+            _ => Self::Other(s),
+        }
+    }
+}
+
+impl From<OtherNameKind> for String {
+    fn from(kind: OtherNameKind) -> Self {
+        kind.code().to_string()
+    }
+}
+
+impl OtherNameKind {
+    pub fn code(&self) -> &str {
+        match self {
+            Self::BN => "BN",
+            Self::TRD => "TRD",
+            Self::OTN => "OTN",
+            Self::Other(x) => x,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum Status {
     Active,
     Cancelled,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Status {
+    /// Every status, in the order they're declared.
+    pub const ALL: [Self; 2] = [Self::Active, Self::Cancelled];
+
+    /// The status as it's written, e.g. `Active`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Active => "Active",
+            Self::Cancelled => "Cancelled",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StatusParseError;
+
+impl fmt::Display for StatusParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown status")
+    }
+}
+
+impl Error for StatusParseError {}
+
+impl FromStr for Status {
+    type Err = StatusParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &*s.to_ascii_lowercase() {
+            "act" | "active" => Ok(Self::Active),
+            "can" | "cancelled" => Ok(Self::Cancelled),
+            _ => Err(StatusParseError),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum EntityName {
     Individual {
@@ -53,9 +200,167 @@ pub enum EntityName {
     NonIndividual {
         name: String,
     },
+    /// An individual whose name has been removed for privacy, leaving a keyed hash of it if
+    /// records still need to be linked.
+    Redacted {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hash: Option<String>,
+    },
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl EntityName {
+    /// The `type` each kind of name is tagged with, in the order they're declared.
+    pub const TYPES: [&'static str; 3] = ["Individual", "NonIndividual", "Redacted"];
+
+    /// The position of this name's `type` in `TYPES`.
+    pub fn type_index(&self) -> usize {
+        match self {
+            Self::Individual { .. } => 0,
+            Self::NonIndividual { .. } => 1,
+            Self::Redacted { .. } => 2,
+        }
+    }
+
+    /// The `type` this name is tagged with, e.g. `NonIndividual`.
+    pub fn type_name(&self) -> &'static str {
+        Self::TYPES[self.type_index()]
+    }
+
+    /// The name as written, with individuals' given names before their family name and no title.
+    /// Redacted names are empty, as are their display and sort names.
+    pub fn full_name(&self) -> String {
+        match self {
+            Self::Individual {
+                title: _,
+                given,
+                given_2,
+                family,
+            } => [given.as_deref(), given_2.as_deref(), Some(family.as_str())]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" "),
+            Self::NonIndividual { name } => name.clone(),
+            Self::Redacted { .. } => String::new(),
+        }
+    }
+
+    /// The name for showing to people, e.g. "Dr Mary-Jane McDonald". Individuals' names are
+    /// title cased, and other names are left as registered since they're full of acronyms.
+    pub fn display_name(&self) -> String {
+        match self {
+            Self::Individual {
+                title,
+                given,
+                given_2,
+                family,
+            } => [title, given, given_2]
+                .into_iter()
+                .flatten()
+                .chain([family])
+                .map(|x| title_case(x))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Self::NonIndividual { name } => name.clone(),
+            Self::Redacted { .. } => String::new(),
+        }
+    }
+
+    /// The name for sorting lists of people by family name, e.g. "MCDONALD, Mary-Jane Anne".
+    pub fn sort_name(&self) -> String {
+        match self {
+            Self::Individual {
+                title: _,
+                given,
+                given_2,
+                family,
+            } => {
+                let given: Vec<_> = [given, given_2]
+                    .into_iter()
+                    .flatten()
+                    .map(|x| title_case(x))
+                    .collect();
+                if given.is_empty() {
+                    family.to_uppercase()
+                } else {
+                    format!("{}, {}", family.to_uppercase(), given.join(" "))
+                }
+            }
+            Self::NonIndividual { name } => name.clone(),
+            Self::Redacted { .. } => String::new(),
+        }
+    }
+}
+
+// Mac names that aren't Mac followed by a capitalised name
+const NOT_MAC: &[&str] = &[
+    "MACE",
+    "MACEY",
+    "MACHADO",
+    "MACHAR",
+    "MACHEN",
+    "MACHIN",
+    "MACHLIN",
+    "MACIAS",
+    "MACIN",
+    "MACKEN",
+    "MACKIE",
+    "MACKINTOSH",
+    "MACKLE",
+    "MACKLIN",
+    "MACKMIN",
+    "MACOMBER",
+    "MACQUARIE",
+];
+
+/// Title cases a person's name, keeping the capitals in names like McDonald, MacLeod, O'Brien
+/// and Smith-Jones.
+pub fn title_case(name: &str) -> String {
+    name.split(' ')
+        .map(|word| {
+            word.split('-')
+                .map(title_case_part)
+                .collect::<Vec<_>>()
+                .join("-")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn title_case_part(part: &str) -> String {
+    let upper = part.to_uppercase();
+    let letters: Vec<_> = part.chars().flat_map(char::to_lowercase).collect();
+
+    // where the second capital goes, if there is one
+    let second = if let Some((prefix, _)) = upper.split_once('\'') {
+        (prefix.chars().count() == 1).then_some(2)
+    } else if upper.starts_with("MC") && letters.len() > 2 {
+        Some(2)
+    } else if upper.starts_with("MAC")
+        && letters.len() > 5
+        && !letters.last().is_some_and(|x| "aciozj".contains(*x))
+        && !NOT_MAC.contains(&upper.as_str())
+    {
+        Some(3)
+    } else {
+        None
+    };
+
+    letters
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            if i == 0 || Some(i) == second {
+                x.to_uppercase().collect()
+            } else {
+                x.to_string()
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub enum EntityType {
     IND, // Individual/Sole Trader
     PRV, // Australian Private Company
@@ -249,3 +554,183 @@ impl FromStr for EntityType {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityCategory {
+    Individual,
+    Company,
+    Partnership,
+    Trust,
+    SuperFund,
+    Government,
+    Other,
+}
+
+impl EntityType {
+    /// Every entity type, in the order they're declared.
+    pub const ALL: [Self; 85] = {
+        use EntityType::*;
+        [
+            IND, PRV, FPT, SMF, DIT, DTT, PTR, FUT, OIE, TRT, STR, UIE, DST, PUB, DES, FXT, HYT,
+            SGE, LPT, PQT, SAF, CUT, COP, NPF, PTT, CMT, NRF, LGE, CGE, PUT, SGA, POF, TGE, PST,
+            SCO, SCN, SSS, CGA, ADF, CSS, LGA, STU, SCR, TGA, LSS, CCN, STI, SUP, PDF, LCN, SCB,
+            LCR, SGP, TTF, SGC, CCO, LGC, CCR, CCB, TSS, TCO, STF, LCO, TTI, SSP, SGT, SCC, LTI,
+            LSP, CTI, CSF, CSA, CGP, TTU, TCN, STD, LTT, LGP, LCS, FHS, CTQ, CTF, CTD, CSP, CGC,
+        ]
+    };
+
+    /// The description the ABR gives alongside the code, as in `EntityTypeText`.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::IND => "Individual/Sole Trader",
+            Self::PRV => "Australian Private Company",
+            Self::FPT => "Family Partnership",
+            Self::SMF => "ATO Regulated Self-Managed Superannuation Fund",
+            Self::DIT => "Discretionary Investment Trust",
+            Self::DTT => "Discretionary Trading Trust",
+            Self::PTR => "Other Partnership",
+            Self::FUT => "Fixed Unit Trust",
+            Self::OIE => "Other Incorporated Entity",
+            Self::TRT => "Other trust",
+            Self::STR => "Strata-title",
+            Self::UIE => "Other Unincorporated Entity",
+            Self::DST => "Discretionary Services Management Trust",
+            Self::PUB => "Australian Public Company",
+            Self::DES => "Deceased Estate",
+            Self::FXT => "Fixed Trust",
+            Self::HYT => "Hybrid Trust",
+            Self::SGE => "State Government Entity",
+            Self::LPT => "Limited Partnership",
+            Self::PQT => "Unlisted Public Unit Trust",
+            Self::SAF => "Small APRA Fund",
+            Self::CUT => "Corporate Unit Trust",
+            Self::COP => "Co-operative",
+            Self::NPF => "APRA Regulated Non-Public Offer Fund",
+            Self::PTT => "Public Trading trust",
+            Self::CMT => "Cash Management Trust",
+            Self::NRF => "Non-Regulated Superannuation Fund",
+            Self::LGE => "Local Government Entity",
+            Self::CGE => "Commonwealth Government Entity",
+            Self::PUT => "Listed Public Unit Trust",
+            Self::SGA => "State Government Statutory Authority",
+            Self::POF => "APRA Regulated Public Offer Fund",
+            Self::TGE => "Territory Government Entity",
+            Self::PST => "Pooled Superannuation Trust",
+            Self::SCO => "State Government Other Incorporated Entity",
+            Self::SCN => "State Government Other Unincorporated Entity",
+            Self::SSS => "State Government Non-Regulated Super Fund",
+            Self::CGA => "Commonwealth Government Statutory Authority",
+            Self::ADF => "Approved Deposit Fund",
+            Self::CSS => "Commonwealth Government Non-Regulated Super Fund",
+            Self::LGA => "Local Government Statutory Authority",
+            Self::STU => "State Government Fixed Unit Trust",
+            Self::SCR => "State Government Private Company",
+            Self::TGA => "Territory Government Statutory Authority",
+            Self::LSS => "Local Government Non-Regulated Super Fund",
+            Self::CCN => "Commonwealth Government Other Unincorporated Entity",
+            Self::STI => "State Government Discretionary Investment Trust",
+            Self::SUP => "Super Fund",
+            Self::PDF => "Pooled Development Fund",
+            Self::LCN => "Local Government Other Unincorporated Entity",
+            Self::SCB => "State Government Public Company",
+            Self::LCR => "Local Government Private Company",
+            Self::SGP => "State Government Partnership",
+            Self::TTF => "Territory Government Fixed Trust",
+            Self::SGC => "State Government Company",
+            Self::CCO => "Commonwealth Government Other Incorporated Entity",
+            Self::LGC => "Local Government Company",
+            Self::CCR => "Commonwealth Government Private Company",
+            Self::CCB => "Commonwealth Government Public Company",
+            Self::TSS => "Territory Government Non-Regulated Super Fund",
+            Self::TCO => "Territory Government Other Incorporated Entity",
+            Self::STF => "State Government Fixed Trust",
+            Self::LCO => "Local Government Other Incorporated Entity",
+            Self::TTI => "Territory Government Discretionary Investment Trust",
+            Self::SSP => "State Government APRA Regulated Public Sector Scheme",
+            Self::SGT => "State Government Trust",
+            Self::SCC => "State Government Co-operative",
+            Self::LTI => "Local Government Discretionary Investment Trust",
+            Self::LSP => "Local Government APRA Regulated Public Sector Scheme",
+            Self::CTI => "Commonwealth Government Discretionary Investment Trust",
+            Self::CSF => "Corporate Collective Investment Vehicle (CCIV) Sub-Fund",
+            Self::CSA => "Commonwealth Government APRA Regulated Public Sector Fund",
+            Self::CGP => "Commonwealth Government Partnership",
+            Self::TTU => "Territory Government Fixed Unit Trust",
+            Self::TCN => "Territory Government Other Unincorporated Entity",
+            Self::STD => "State Government Discretionary Services Management Trust",
+            Self::LTT => "Local Government Discretionary Trading Trust",
+            Self::LGP => "Local Government Partnership",
+            Self::LCS => "Local Government Strata Title",
+            Self::FHS => "First Home Saver Accounts Trust",
+            Self::CTQ => "Commonwealth Government Unlisted Public Unit Trust",
+            Self::CTF => "Commonwealth Government Fixed Trust",
+            Self::CTD => "Commonwealth Government Discretionary Services Management Trust",
+            Self::CSP => "Commonwealth Government APRA Regulated Public Sector Scheme",
+            Self::CGC => "Commonwealth Government Company",
+        }
+    }
+
+    pub fn category(self) -> EntityCategory {
+        use EntityType::*;
+        match self {
+            IND => EntityCategory::Individual,
+            PRV | PUB | PDF => EntityCategory::Company,
+            FPT | PTR | LPT => EntityCategory::Partnership,
+            DIT | DTT | FUT | TRT | DST | FXT | HYT | PQT | CUT | PTT | CMT | PUT | FHS | CSF => {
+                EntityCategory::Trust
+            }
+            SMF | SAF | NPF | NRF | POF | PST | ADF | SUP => EntityCategory::SuperFund,
+            SGE | LGE | CGE | TGE | SGA | SCO | SCN | SSS | CGA | CSS | LGA | STU | SCR | TGA
+            | LSS | CCN | STI | LCN | SCB | LCR | SGP | TTF | SGC | CCO | LGC | CCR | CCB | TSS
+            | TCO | STF | LCO | TTI | SSP | SGT | SCC | LTI | LSP | CTI | CSA | CGP | TTU | TCN
+            | STD | LTT | LGP | LCS | CTQ | CTF | CTD | CSP | CGC => EntityCategory::Government,
+            OIE | STR | UIE | DES | COP => EntityCategory::Other,
+        }
+    }
+
+    /// The kind of trust this type of entity is, if it's a trust or super fund.
+    pub fn trust_kind(self) -> Option<TrustKind> {
+        use EntityType::*;
+        match self {
+            DIT | DTT | DST | STI | TTI | LTI | CTI | STD | CTD | LTT => {
+                Some(TrustKind::Discretionary)
+            }
+            FUT | PQT | PUT | CUT | STU | TTU | CTQ => Some(TrustKind::Unit),
+            FXT | STF | TTF | CTF => Some(TrustKind::Fixed),
+            HYT => Some(TrustKind::Hybrid),
+            SMF | SAF | NPF | NRF | POF | PST | ADF | SUP | SSS | CSS | LSS | TSS | SSP | LSP
+            | CSA | CSP => Some(TrustKind::SuperFund),
+            TRT | PTT | CMT | FHS | CSF | SGT => Some(TrustKind::Other),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EntityCategoryParseError;
+
+impl fmt::Display for EntityCategoryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown entity category")
+    }
+}
+
+impl Error for EntityCategoryParseError {}
+
+impl FromStr for EntityCategory {
+    type Err = EntityCategoryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &*s.to_ascii_lowercase() {
+            "individual" => Ok(Self::Individual),
+            "company" => Ok(Self::Company),
+            "partnership" => Ok(Self::Partnership),
+            "trust" => Ok(Self::Trust),
+            "super_fund" => Ok(Self::SuperFund),
+            "government" => Ok(Self::Government),
+            "other" => Ok(Self::Other),
+            _ => Err(EntityCategoryParseError),
+        }
+    }
+}
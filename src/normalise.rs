@@ -0,0 +1,77 @@
+//! Canonical forms of names, so the same business written differently compares equal, e.g.
+//! "The Smith & Sons Pty. Ltd." and "SMITH AND SONS PROPRIETARY LIMITED".
+
+use crate::model::EntityName;
+
+/// Splits a name into upper case words, dropping punctuation and apostrophes, reading "&" as
+/// "AND", and spelling legal forms one way.
+pub fn tokens(name: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut push = |token: String| {
+        tokens.push(match token.as_str() {
+            "PROPRIETARY" => "PTY".into(),
+            "LIMITED" => "LTD".into(),
+            "INCORPORATED" => "INC".into(),
+            _ => token,
+        })
+    };
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            token.extend(c.to_uppercase());
+        } else if c == '&' {
+            if !token.is_empty() {
+                push(std::mem::take(&mut token));
+            }
+            push("AND".into());
+        } else if c != '\'' && !token.is_empty() {
+            push(std::mem::take(&mut token));
+        }
+    }
+    if !token.is_empty() {
+        push(token);
+    }
+    tokens
+}
+
+/// A key that's the same for every way of writing a name, e.g. "SMITH AND SONS PTY LTD".
+///
+/// On top of `tokens`, this drops "THE" and a leading "THE TRUSTEE FOR", which trusts are
+/// registered under but people rarely write.
+pub fn match_key(name: &str) -> String {
+    key_tokens(name).join(" ")
+}
+
+/// The match key of an entity's name, with individuals' given names before their family name.
+pub fn entity_match_key(name: &EntityName) -> String {
+    match name {
+        EntityName::Individual { .. } | EntityName::Redacted { .. } => match_key(&name.full_name()),
+        EntityName::NonIndividual { name } => match_key(name),
+    }
+}
+
+/// The match key without trailing legal forms and trust types, so "SMITH AND SONS PTY LTD"
+/// and "SMITH AND SONS" compare equal too, e.g. when matching free-text names.
+pub fn core_key(name: &str) -> String {
+    let mut tokens = key_tokens(name);
+    while tokens.len() > 1
+        && matches!(
+            tokens.last().map(|x| x.as_str()),
+            Some("PTY" | "LTD" | "INC" | "TRUST" | "UNIT" | "DISCRETIONARY")
+        )
+    {
+        tokens.pop();
+    }
+    tokens.join(" ")
+}
+
+fn key_tokens(name: &str) -> Vec<String> {
+    let mut tokens = tokens(name);
+    if tokens.starts_with(&["THE".into(), "TRUSTEE".into(), "FOR".into()]) {
+        tokens.drain(..3);
+    } else if tokens.starts_with(&["TRUSTEE".into(), "FOR".into()]) {
+        tokens.drain(..2);
+    }
+    tokens.retain(|x| x != "THE");
+    tokens
+}
@@ -0,0 +1,369 @@
+//! Where converted records go: stdout, a file compressed according to its extension, or a set
+//! of files split by a key of each record. Files ending in `.csv` (before any compression
+//! extension) are written as CSV instead of JSON lines.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, ensure, Context, Error, Result};
+use clap::Args;
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::model::{ABNRecord, Status};
+
+/// Somewhere to write converted records, one line each.
+pub trait Output {
+    fn write_record(&mut self, record: &ABNRecord, line: &str) -> Result<()>;
+
+    /// Makes sure everything written so far is in the file, returning how much that is, or None
+    /// if the output can't be resumed.
+    fn checkpoint(&mut self) -> Result<Option<Written>> {
+        Ok(None)
+    }
+
+    /// Called after each chunk of records, for outputs that write them in batches.
+    fn end_chunk(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: Write> Output for W {
+    fn write_record(&mut self, _: &ABNRecord, line: &str) -> Result<()> {
+        writeln!(self, "{line}")?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Args)]
+pub struct CompressionArgs {
+    /// Compression level for --output, 1-22 for zstd (default 3) or 0-9 for gzip (default 6)
+    #[arg(long)]
+    pub compression_level: Option<u32>,
+    /// Threads to compress each zstd file with, by default all cores, or one per file when
+    /// sharding
+    #[arg(long)]
+    pub zstd_threads: Option<u32>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+    /// Arrow IPC, written by `arrow::ArrowWriter` rather than line by line
+    Arrow,
+    /// A psql script and COPY data files, written by `postgres::PostgresWriter`
+    Postgres,
+}
+
+impl Format {
+    /// CSV for paths like `out.csv` or `out.csv.zst`, Arrow for `out.arrow`, `out.arrows` or
+    /// `out.feather`, PostgreSQL for `out.sql`, and JSON lines otherwise. Arrow and PostgreSQL
+    /// output can't be compressed this way.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let (path, compressed) = match path.extension().and_then(|x| x.to_str()) {
+            Some("zst" | "gz") => (Path::new(path.file_stem().unwrap_or_default()), true),
+            _ => (path, false),
+        };
+        Ok(match path.extension().and_then(|x| x.to_str()) {
+            Some("csv") => Self::Csv,
+            Some("arrow" | "arrows" | "feather") => {
+                ensure!(
+                    !compressed,
+                    "Arrow output can't be compressed with .zst or .gz"
+                );
+                Self::Arrow
+            }
+            Some("sql") => {
+                ensure!(
+                    !compressed,
+                    "PostgreSQL output can't be compressed with .zst or .gz"
+                );
+                Self::Postgres
+            }
+            _ => Self::Json,
+        })
+    }
+}
+
+/// A file that's compressed with zstd if its name ends in `.zst`, gzip if it ends in `.gz`,
+/// and left as is otherwise.
+///
+/// Each checkpoint ends a zstd frame or gzip member and starts another, so the file can be cut
+/// back to a checkpoint and appended to. zstd and gzip read the parts back as one stream.
+pub struct Writer {
+    /// Only missing while a frame is being ended
+    stream: Option<Stream>,
+    codec: Codec,
+    records: u64,
+}
+
+enum Stream {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+#[derive(Clone, Copy)]
+enum Codec {
+    Plain,
+    Gzip(Compression),
+    Zstd { level: i32, threads: u32 },
+}
+
+impl Codec {
+    fn new(path: &Path, compression: CompressionArgs, threads: u32) -> Result<Self> {
+        let level = compression.compression_level;
+        Ok(match path.extension().and_then(|x| x.to_str()) {
+            Some("zst") => {
+                let level = level.unwrap_or(3);
+                ensure!((1..=22).contains(&level), "zstd levels go from 1 to 22");
+                Self::Zstd {
+                    level: level as i32,
+                    threads,
+                }
+            }
+            Some("gz") => {
+                let level = level.unwrap_or(6);
+                ensure!(level <= 9, "gzip levels go from 0 to 9");
+                Self::Gzip(Compression::new(level))
+            }
+            _ => {
+                ensure!(
+                    level.is_none(),
+                    "--compression-level needs a .zst or .gz output"
+                );
+                Self::Plain
+            }
+        })
+    }
+
+    fn stream(self, file: BufWriter<File>) -> Result<Stream> {
+        Ok(match self {
+            Self::Plain => Stream::Plain(file),
+            Self::Gzip(level) => Stream::Gzip(GzEncoder::new(file, level)),
+            Self::Zstd { level, threads } => {
+                let mut encoder = zstd::Encoder::new(file, level)?;
+                if threads > 1 {
+                    encoder.multithread(threads)?;
+                }
+                Stream::Zstd(encoder)
+            }
+        })
+    }
+}
+
+impl Stream {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Plain(x) => x,
+            Self::Gzip(x) => x,
+            Self::Zstd(x) => x,
+        }
+    }
+
+    fn finish(self) -> Result<BufWriter<File>> {
+        Ok(match self {
+            Self::Plain(x) => x,
+            Self::Gzip(x) => x.finish()?,
+            Self::Zstd(x) => x.finish()?,
+        })
+    }
+}
+
+impl Writer {
+    /// Creates the file, starting it with a header line if there is one.
+    pub fn create(
+        path: &Path,
+        compression: CompressionArgs,
+        threads: u32,
+        header: Option<&str>,
+    ) -> Result<Self> {
+        let codec = Codec::new(path, compression, threads)?;
+        let file = File::create(path).with_context(|| format!("failed to create {path:?}"))?;
+        let mut stream = codec.stream(BufWriter::new(file))?;
+        if let Some(header) = header {
+            writeln!(stream.writer(), "{header}")?;
+        }
+        Ok(Self {
+            stream: Some(stream),
+            codec,
+            records: 0,
+        })
+    }
+
+    /// Opens a file written up to a checkpoint to carry on writing it, cutting off anything
+    /// written after the checkpoint.
+    pub fn append(
+        path: &Path,
+        compression: CompressionArgs,
+        threads: u32,
+        checkpoint: Written,
+    ) -> Result<Self> {
+        let codec = Codec::new(path, compression, threads)?;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .with_context(|| format!("failed to open {path:?}"))?;
+        ensure!(
+            file.metadata()?.len() >= checkpoint.bytes,
+            "{path:?} is shorter than its checkpoint, so it can't be resumed"
+        );
+        file.set_len(checkpoint.bytes)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            stream: Some(codec.stream(BufWriter::new(file))?),
+            codec,
+            records: checkpoint.records,
+        })
+    }
+
+    /// Writes out the end of the compressed stream and flushes the file, which has to be done
+    /// explicitly to see any errors. Returns how much was written in all.
+    pub fn finish(mut self) -> Result<Written> {
+        let mut file = self.stream.take().expect("stream is present").finish()?;
+        file.flush()?;
+        Ok(Written {
+            bytes: file.stream_position()?,
+            records: self.records,
+        })
+    }
+}
+
+impl Output for Writer {
+    fn write_record(&mut self, _: &ABNRecord, line: &str) -> Result<()> {
+        let stream = self.stream.as_mut().expect("stream is present");
+        writeln!(stream.writer(), "{line}")?;
+        self.records += 1;
+        Ok(())
+    }
+
+    fn checkpoint(&mut self) -> Result<Option<Written>> {
+        let mut file = self.stream.take().expect("stream is present").finish()?;
+        file.flush()?;
+        file.get_ref().sync_data()?;
+        let bytes = file.stream_position()?;
+        self.stream = Some(self.codec.stream(file)?);
+        Ok(Some(Written {
+            bytes,
+            records: self.records,
+        }))
+    }
+}
+
+/// How much of an output had been written at a checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Written {
+    pub bytes: u64,
+    pub records: u64,
+}
+
+/// What to split output files by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardKey {
+    State,
+    EntityType,
+    Status,
+    /// The first digit of the ABN, which splits records into nine similar sized ranges
+    Abn,
+}
+
+impl ShardKey {
+    pub fn shard(self, record: &ABNRecord) -> String {
+        match self {
+            Self::State => record.state.clone().unwrap_or_else(|| "none".into()),
+            Self::EntityType => format!("{:?}", record.entity_type),
+            Self::Status => match record.status {
+                Status::Active => "active".into(),
+                Status::Cancelled => "cancelled".into(),
+            },
+            Self::Abn => record.abn.chars().take(1).collect(),
+        }
+    }
+}
+
+impl FromStr for ShardKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "state" => Ok(Self::State),
+            "type" => Ok(Self::EntityType),
+            "status" => Ok(Self::Status),
+            "abn" => Ok(Self::Abn),
+            _ => bail!("unknown shard key, expected state, type, status or abn"),
+        }
+    }
+}
+
+/// A file for each shard, named by replacing `{}` in a template with the shard, e.g.
+/// `out/{}.jsonl.zst`. Files are created as records for them turn up.
+pub struct Shards {
+    template: String,
+    key: ShardKey,
+    compression: CompressionArgs,
+    header: Option<String>,
+    files: BTreeMap<String, Writer>,
+}
+
+impl Shards {
+    pub fn new(
+        template: &str,
+        key: ShardKey,
+        compression: CompressionArgs,
+        header: Option<String>,
+    ) -> Result<Self> {
+        ensure!(
+            template.matches("{}").count() == 1,
+            "sharded output needs one {{}} in its path for the shard name"
+        );
+        Ok(Self {
+            template: template.into(),
+            key,
+            compression,
+            header,
+            files: BTreeMap::new(),
+        })
+    }
+
+    /// Finishes every file, returning their paths and how much was written to each.
+    pub fn finish(self) -> Result<Vec<(PathBuf, Written)>> {
+        let mut written = Vec::new();
+        for (shard, file) in self.files {
+            written.push((self.template.replace("{}", &shard).into(), file.finish()?));
+        }
+        Ok(written)
+    }
+}
+
+impl Output for Shards {
+    fn write_record(&mut self, record: &ABNRecord, line: &str) -> Result<()> {
+        let shard = self.key.shard(record);
+        let file = match self.files.get_mut(&shard) {
+            Some(x) => x,
+            None => {
+                // shard names come from the data, so keep them to something safe in a path
+                ensure!(
+                    !shard.is_empty() && shard.chars().all(|x| x.is_ascii_alphanumeric()),
+                    "invalid shard name: {shard:?}"
+                );
+                let path = self.template.replace("{}", &shard);
+                let path = Path::new(&path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                // there can be dozens of shards open at once, so they share the cores
+                let threads = self.compression.zstd_threads.unwrap_or(1);
+                let file = Writer::create(path, self.compression, threads, self.header.as_deref())?;
+                self.files.entry(shard).or_insert(file)
+            }
+        };
+        file.write_record(record, line)
+    }
+}
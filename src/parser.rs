@@ -1,16 +1,25 @@
-use std::str::FromStr;
+use std::{borrow::Cow, str::FromStr};
 
 use anyhow::{bail, ensure, Context, Result};
 use chrono::NaiveDate;
-use xml::{attribute::OwnedAttribute, reader::XmlEvent, EventReader};
 
-use crate::model::{ABNRecord, EntityName, EntityType, Status};
+use crate::{
+    model::{
+        ABNRecord, EntityName, EntityType, OtherName, OtherNameKind, Status, Trust, TrustKind,
+    },
+    xml::{Attributes, Event, Reader},
+};
+
+// deep enough for ABR/MainEntity/BusinessAddress/AddressDetails/State
+const MAX_DEPTH: usize = 8;
 
 pub fn parse_record(xml: &str) -> Result<ABNRecord> {
     let mut helper = Helper::default();
-    for e in EventReader::from_str(xml) {
-        helper.handle(e?)?;
+    let mut reader = Reader::new(xml);
+    while let Some(e) = reader.read_event()? {
+        helper.handle(e)?;
     }
+    ensure!(helper.depth == 0, "unexpected end of record");
 
     let replaced = helper
         .replaced
@@ -18,7 +27,7 @@ pub fn parse_record(xml: &str) -> Result<ABNRecord> {
         .context("invalid replaced")?;
     ensure!(!replaced, "replaced was ignored as it was never true");
 
-    let abn = helper.abn.context("missing abn")?;
+    let abn = helper.abn.context("missing abn")?.into_owned();
     let status = match &*helper.abn_status.context("missing abn status")? {
         "ACT" => Status::Active,
         "CAN" => Status::Cancelled,
@@ -34,10 +43,14 @@ pub fn parse_record(xml: &str) -> Result<ABNRecord> {
     )?;
 
     // TODO: some have a postcode but no state
-    let postcode = helper
-        .postcode
-        .and_then(|x| if x == "0000" { None } else { Some(x) });
-    let state = helper.state;
+    let postcode = helper.postcode.and_then(|x| {
+        if x == "0000" {
+            None
+        } else {
+            Some(x.into_owned())
+        }
+    });
+    let state = helper.state.map(Cow::into_owned);
 
     let asic_number = match helper.asic_number {
         Some(x) => {
@@ -45,7 +58,7 @@ pub fn parse_record(xml: &str) -> Result<ABNRecord> {
                 helper.asic_number_type.is_some_and(|x| x == "undetermined"),
                 "unexpected asic number type"
             );
-            Some(x)
+            Some(x.into_owned())
         }
         None => {
             ensure!(
@@ -86,12 +99,13 @@ pub fn parse_record(xml: &str) -> Result<ABNRecord> {
 
     let entity_name = match helper.individual_name_type.as_deref() {
         Some("LGL") => EntityName::Individual {
-            title: helper.individual_name_title,
-            given: helper.individual_name_given_1,
-            given_2: helper.individual_name_given_2,
+            title: helper.individual_name_title.map(Cow::into_owned),
+            given: helper.individual_name_given_1.map(Cow::into_owned),
+            given_2: helper.individual_name_given_2.map(Cow::into_owned),
             family: helper
                 .individual_name_family
-                .context("missing family name")?,
+                .context("missing family name")?
+                .into_owned(),
         },
         Some(x) => bail!("unexpected individual name type: {x}"),
         None => {
@@ -100,20 +114,23 @@ pub fn parse_record(xml: &str) -> Result<ABNRecord> {
                 "unexpected individual name type"
             );
             EntityName::NonIndividual {
-                name: helper.non_individual_name.context("missing name")?,
+                name: helper
+                    .non_individual_name
+                    .context("missing name")?
+                    .into_owned(),
             }
         }
     };
 
-    let mut business_names = Vec::new();
-    let mut trade_names = Vec::new();
+    let trust = trust(&entity_name, entity_type);
+
+    let mut other_names = Vec::new();
     for (name, t) in helper.other_names.into_iter().zip(helper.other_name_types) {
-        match &*t {
-            // OTN - old trade name? definitely linked to pre-2012 and shown under trade names on website so im putting them here for now
-            "TRD" | "OTN" => trade_names.push(name),
-            "BN" => business_names.push(name),
-            _ => bail!("unknown name type {t}"),
-        }
+        ensure!(!t.is_empty(), "empty name type");
+        other_names.push(OtherName {
+            name: name.into_owned(),
+            kind: OtherNameKind::from(t.into_owned()),
+        });
     }
 
     Ok(ABNRecord {
@@ -123,8 +140,8 @@ pub fn parse_record(xml: &str) -> Result<ABNRecord> {
         last_updated,
         entity_name,
         entity_type,
-        business_names,
-        trade_names,
+        trust,
+        other_names,
         postcode,
         state,
         asic_number,
@@ -134,65 +151,68 @@ pub fn parse_record(xml: &str) -> Result<ABNRecord> {
 }
 
 #[derive(Debug, Default)]
-struct Helper {
-    path: Vec<String>,
+struct Helper<'a> {
+    path: [&'a str; MAX_DEPTH],
+    depth: usize,
 
-    last_updated: Option<String>,
-    replaced: Option<String>,
+    last_updated: Option<Cow<'a, str>>,
+    replaced: Option<Cow<'a, str>>,
 
-    abn: Option<String>,
-    abn_status: Option<String>,
-    abn_status_since: Option<String>,
+    abn: Option<Cow<'a, str>>,
+    abn_status: Option<Cow<'a, str>>,
+    abn_status_since: Option<Cow<'a, str>>,
 
-    entity_type: Option<String>,
-    entity_type_id: Option<String>,
+    entity_type: Option<Cow<'a, str>>,
+    entity_type_id: Option<Cow<'a, str>>,
 
-    individual_name_title: Option<String>,
-    individual_name_given_1: Option<String>,
-    individual_name_given_2: Option<String>,
-    individual_name_family: Option<String>,
-    individual_name_type: Option<String>,
-    non_individual_name: Option<String>,
-    non_individual_name_type: Option<String>,
-    other_names: Vec<String>,
-    other_name_types: Vec<String>,
+    individual_name_title: Option<Cow<'a, str>>,
+    individual_name_given_1: Option<Cow<'a, str>>,
+    individual_name_given_2: Option<Cow<'a, str>>,
+    individual_name_family: Option<Cow<'a, str>>,
+    individual_name_type: Option<Cow<'a, str>>,
+    non_individual_name: Option<Cow<'a, str>>,
+    non_individual_name_type: Option<Cow<'a, str>>,
+    other_names: Vec<Cow<'a, str>>,
+    other_name_types: Vec<Cow<'a, str>>,
 
-    dgr_dates: Vec<String>,
-    dgr_names: Vec<String>,
+    dgr_dates: Vec<Cow<'a, str>>,
+    dgr_names: Vec<Cow<'a, str>>,
 
-    state: Option<String>,
-    postcode: Option<String>,
+    state: Option<Cow<'a, str>>,
+    postcode: Option<Cow<'a, str>>,
 
-    asic_number: Option<String>,
-    asic_number_type: Option<String>,
+    asic_number: Option<Cow<'a, str>>,
+    asic_number_type: Option<Cow<'a, str>>,
 
-    gst_status: Option<String>,
-    gst_status_since: Option<String>,
+    gst_status: Option<Cow<'a, str>>,
+    gst_status_since: Option<Cow<'a, str>>,
 }
 
-impl Helper {
-    pub fn handle(&mut self, e: XmlEvent) -> Result<()> {
+impl<'a> Helper<'a> {
+    pub fn handle(&mut self, e: Event<'a>) -> Result<()> {
         self.handle_(e)
-            .with_context(|| format!("in {:?}", self.path))
+            .with_context(|| format!("in {:?}", self.path()))
     }
 
-    fn handle_(&mut self, e: XmlEvent) -> Result<()> {
+    fn handle_(&mut self, e: Event<'a>) -> Result<()> {
         match e {
-            XmlEvent::StartElement {
-                name,
-                attributes,
-                namespace: _,
-            } => {
-                self.path.push(name.local_name);
+            Event::Start { name, attributes } => {
+                ensure!(self.depth < MAX_DEPTH, "too deeply nested");
+                self.path[self.depth] = name;
+                self.depth += 1;
 
                 self.handle_attrs(attributes)?;
             }
-            XmlEvent::EndElement { name: _ } => {
-                self.path.pop();
+            Event::End { name } => {
+                ensure!(
+                    self.path().last() == Some(&name),
+                    "unexpected end of {name}"
+                );
+                self.depth -= 1;
             }
-            XmlEvent::Characters(x) => {
+            Event::Text(x) => {
                 let path = self.path();
-                match path[..] {
+                match path {
                     ["ABR", "ABN"] => set(&mut self.abn, x)?,
 
                     ["ABR", "EntityType", "EntityTypeInd"] => set(&mut self.entity_type_id, x)?,
@@ -236,20 +256,19 @@ impl Helper {
                     _ => eprintln!("unhandled text: {path:?}: {x}"),
                 }
             }
-
-            _ => (),
         }
 
         Ok(())
     }
 
-    fn path(&self) -> Vec<&str> {
-        self.path.iter().map(|s| s.as_str()).collect()
+    fn path(&self) -> &[&'a str] {
+        &self.path[..self.depth]
     }
 
-    fn handle_attrs(&mut self, attrs: Vec<OwnedAttribute>) -> Result<()> {
-        for (k, v) in attrs.into_iter().map(|x| (x.name.local_name, x.value)) {
-            match (&self.path()[..], &*k) {
+    fn handle_attrs(&mut self, attrs: Attributes<'a>) -> Result<()> {
+        for attr in attrs {
+            let (k, v) = attr?;
+            match (self.path(), k) {
                 (["ABR"], "recordLastUpdatedDate") => set(&mut self.last_updated, v)?,
                 (["ABR"], "replaced") => set(&mut self.replaced, v)?,
                 (["ABR", "ABN"], "status") => set(&mut self.abn_status, v)?,
@@ -279,6 +298,34 @@ impl Helper {
     }
 }
 
+/// Splits the trust's own name out of a trustee's, for trusts and anything registered as a
+/// trustee.
+fn trust(entity_name: &EntityName, entity_type: EntityType) -> Option<Trust> {
+    let EntityName::NonIndividual { name } = entity_name else {
+        return None;
+    };
+    let kind = entity_type.trust_kind();
+
+    let trust_name = ["THE TRUSTEE FOR ", "TRUSTEE FOR "]
+        .into_iter()
+        .find_map(|prefix| {
+            name.get(..prefix.len())
+                .filter(|x| x.eq_ignore_ascii_case(prefix))
+                .map(|_| name[prefix.len()..].trim())
+        });
+    match (trust_name, kind) {
+        (Some(x), _) if !x.is_empty() => Some(Trust {
+            name: x.to_string(),
+            kind: kind.unwrap_or(TrustKind::Other),
+        }),
+        (_, Some(kind)) => Some(Trust {
+            name: name.clone(),
+            kind,
+        }),
+        _ => None,
+    }
+}
+
 fn set<T>(o: &mut Option<T>, x: T) -> Result<()> {
     if o.is_some() {
         bail!("already set")
@@ -0,0 +1,373 @@
+//! PostgreSQL output: a psql script that creates tables for records and loads them with
+//! `\copy` from data files in COPY's text or binary format, so a warehouse can be loaded without
+//! this tool connecting to it.
+
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::{BufWriter, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+
+use crate::{
+    model::{ABNRecord, EntityName, EntityType, Status, TrustKind, SCHEMA_VERSION},
+    normalise::entity_match_key,
+    output::{Output, Written},
+};
+
+/// Keys of the tables, added once they're loaded as that's quicker than checking each row.
+pub const KEYS: &str = "ALTER TABLE abns ADD PRIMARY KEY (abn);
+ALTER TABLE abn_other_names ADD PRIMARY KEY (abn, position), ADD FOREIGN KEY (abn) REFERENCES abns;
+";
+
+/// Starts a binary COPY file: its signature, flags and header extension length.
+const BINARY_HEADER: &[u8] = b"PGCOPY\n\xff\r\n\0\0\0\0\0\0\0\0\0";
+
+/// A script (e.g. `abns.sql`) with the tables' DDL and `\copy` commands, and a data file next to
+/// it for each table: `abns.copy` for records and `abns_other_names.copy` for their other names.
+pub struct PostgresWriter {
+    script: PathBuf,
+    records: CopyFile,
+    other_names: CopyFile,
+    entity_types: Vec<String>,
+    match_key: bool,
+    display_name: bool,
+}
+
+impl PostgresWriter {
+    /// Writes the script and creates the data files, with `match_key` and `display_name` columns
+    /// if asked for. Data is in COPY's binary format if `binary`, and text otherwise.
+    pub fn create(path: &Path, binary: bool, match_key: bool, display_name: bool) -> Result<Self> {
+        let stem = path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .context("output path isn't valid UTF-8")?;
+        let records = path.with_file_name(format!("{stem}.copy"));
+        let other_names = path.with_file_name(format!("{stem}_other_names.copy"));
+
+        let mut script = format!(
+            "-- simple-abns {}, record schema version {SCHEMA_VERSION}\n\
+             \\set ON_ERROR_STOP on\n\
+             BEGIN;\n\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        script += &ddl(match_key, display_name);
+        let format = if binary { "binary" } else { "text" };
+        script += "\n-- run from this directory, e.g. psql -f ";
+        script += &path.file_name().unwrap_or_default().to_string_lossy();
+        script += "\n";
+        for (table, data) in [("abns", &records), ("abn_other_names", &other_names)] {
+            let name = data.file_name().unwrap_or_default().to_string_lossy();
+            writeln!(
+                script,
+                "\\copy {table} FROM {} WITH (FORMAT {format})",
+                literal(&name)
+            )?;
+        }
+        script += KEYS;
+        script += "COMMIT;\n";
+        fs::write(path, script).with_context(|| format!("failed to write {path:?}"))?;
+
+        Ok(Self {
+            script: path.into(),
+            records: CopyFile::create(records, binary)?,
+            other_names: CopyFile::create(other_names, binary)?,
+            entity_types: EntityType::ALL.iter().map(|x| format!("{x:?}")).collect(),
+            match_key,
+            display_name,
+        })
+    }
+
+    /// Ends the data files, returning what was written to each file.
+    pub fn finish(self) -> Result<Vec<(PathBuf, Written)>> {
+        let script = Written {
+            bytes: fs::metadata(&self.script)?.len(),
+            records: 0,
+        };
+        Ok(vec![
+            (self.script, script),
+            self.records.finish()?,
+            self.other_names.finish()?,
+        ])
+    }
+}
+
+impl Output for PostgresWriter {
+    fn write_record(&mut self, record: &ABNRecord, _: &str) -> Result<()> {
+        let name = &record.entity_name;
+        let (title, given, given_2, family, entity_name, hash) = match name {
+            EntityName::Individual {
+                title,
+                given,
+                given_2,
+                family,
+            } => (
+                title.as_deref(),
+                given.as_deref(),
+                given_2.as_deref(),
+                Some(family.as_str()),
+                None,
+                None,
+            ),
+            EntityName::NonIndividual { name } => {
+                (None, None, None, None, Some(name.as_str()), None)
+            }
+            EntityName::Redacted { hash } => (None, None, None, None, None, hash.as_deref()),
+        };
+        let display_name = self.display_name.then(|| name.display_name());
+        let match_key = self.match_key.then(|| entity_match_key(name));
+        let trust = record.trust.as_ref();
+
+        let mut row = vec![
+            Cell::from(record.abn.as_str()),
+            record.status.label().into(),
+            record.status_since.into(),
+            record.last_updated.into(),
+            name.type_name().into(),
+            title.into(),
+            given.into(),
+            given_2.into(),
+            family.into(),
+            entity_name.into(),
+            hash.into(),
+        ];
+        if let Some(x) = &display_name {
+            row.push(x.as_str().into());
+        }
+        row.extend([
+            self.entity_types[record.entity_type as usize]
+                .as_str()
+                .into(),
+            trust.map(|x| x.name.as_str()).into(),
+            trust.map(|x| x.kind.label()).into(),
+            record.postcode.as_deref().into(),
+            record.state.as_deref().into(),
+            record.asic_number.as_deref().into(),
+            record.gst_status.map(Status::label).into(),
+            record.gst_status_since.into(),
+        ]);
+        if let Some(x) = &match_key {
+            row.push(x.as_str().into());
+        }
+        self.records.row(&row)?;
+
+        for (i, other_name) in record.other_names.iter().enumerate() {
+            self.other_names.row(&[
+                record.abn.as_str().into(),
+                Cell::Int(i as i32),
+                other_name.name.as_str().into(),
+                other_name.kind.code().into(),
+            ])?;
+        }
+        Ok(())
+    }
+}
+
+/// The DDL of the tables, creating an enum type for each of the record's enums, a table of records
+/// with a column for each part of their names, and a child table of other names in order. Their
+/// keys are in `KEYS`.
+pub fn ddl(match_key: bool, display_name: bool) -> String {
+    let entity_types: Vec<_> = EntityType::ALL.iter().map(|x| format!("{x:?}")).collect();
+    let enums = [
+        ("abn_status", Status::ALL.map(Status::label).to_vec()),
+        ("abn_name_type", EntityName::TYPES.to_vec()),
+        (
+            "abn_entity_type",
+            entity_types.iter().map(|x| x.as_str()).collect(),
+        ),
+        (
+            "abn_trust_kind",
+            TrustKind::ALL.map(TrustKind::label).to_vec(),
+        ),
+    ];
+
+    let mut ddl = String::new();
+    for (name, values) in enums {
+        let values: Vec<_> = values.iter().map(|x| literal(x)).collect();
+        ddl += &format!("CREATE TYPE {name} AS ENUM ({});\n", values.join(", "));
+    }
+
+    let mut columns = vec![
+        "abn char(11) NOT NULL",
+        "status abn_status NOT NULL",
+        "status_since date NOT NULL",
+        "last_updated date NOT NULL",
+        "name_type abn_name_type NOT NULL",
+        "title text",
+        "given text",
+        "given_2 text",
+        "family text",
+        "name text",
+        "name_hash text",
+    ];
+    if display_name {
+        columns.push("display_name text NOT NULL");
+    }
+    columns.extend([
+        "entity_type abn_entity_type NOT NULL",
+        "trust_name text",
+        "trust_kind abn_trust_kind",
+        "postcode text",
+        "state text",
+        "asic_number text",
+        "gst_status abn_status",
+        "gst_status_since date",
+    ]);
+    if match_key {
+        columns.push("match_key text NOT NULL");
+    }
+    ddl += &format!(
+        "\nCREATE TABLE abns (\n    {}\n);\n",
+        columns.join(",\n    ")
+    );
+    // kept with the table, so it's known what wrote the data once the script is gone
+    let version = format!(
+        "simple-abns {}, record schema version {SCHEMA_VERSION}",
+        env!("CARGO_PKG_VERSION")
+    );
+    ddl += &format!("COMMENT ON TABLE abns IS {};\n", literal(&version));
+    ddl += "\nCREATE TABLE abn_other_names (\n    \
+            abn char(11) NOT NULL,\n    \
+            position integer NOT NULL,\n    \
+            name text NOT NULL,\n    \
+            kind text NOT NULL\n);\n";
+    ddl
+}
+
+/// A quoted SQL string literal.
+fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// A value of a row, typed as its column is.
+pub enum Cell<'a> {
+    Null,
+    Text(&'a str),
+    Date(NaiveDate),
+    Int(i32),
+}
+
+impl<'a> From<&'a str> for Cell<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<NaiveDate> for Cell<'_> {
+    fn from(value: NaiveDate) -> Self {
+        Self::Date(value)
+    }
+}
+
+impl<'a, T: Into<Cell<'a>>> From<Option<T>> for Cell<'a> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+/// A table's data in COPY's text or binary format.
+struct CopyFile {
+    path: PathBuf,
+    file: BufWriter<File>,
+    binary: bool,
+    rows: u64,
+}
+
+impl CopyFile {
+    fn create(path: PathBuf, binary: bool) -> Result<Self> {
+        let file = File::create(&path).with_context(|| format!("failed to create {path:?}"))?;
+        let mut file = BufWriter::new(file);
+        if binary {
+            file.write_all(BINARY_HEADER)?;
+        }
+        Ok(Self {
+            path,
+            file,
+            binary,
+            rows: 0,
+        })
+    }
+
+    fn row(&mut self, cells: &[Cell]) -> Result<()> {
+        if self.binary {
+            write_binary(&mut self.file, cells)?;
+        } else {
+            write_text(&mut self.file, cells)?;
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(PathBuf, Written)> {
+        if self.binary {
+            // a field count of -1 ends the data
+            self.file.write_all(&(-1i16).to_be_bytes())?;
+        }
+        self.file.flush()?;
+        let written = Written {
+            bytes: self.file.stream_position()?,
+            records: self.rows,
+        };
+        Ok((self.path, written))
+    }
+}
+
+/// Writes a row as tab separated values, with `\N` for nulls and backslash escapes.
+pub fn write_text(out: &mut impl Write, cells: &[Cell]) -> Result<()> {
+    for (i, cell) in cells.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b"\t")?;
+        }
+        match cell {
+            Cell::Null => out.write_all(b"\\N")?,
+            Cell::Text(x) => {
+                let mut rest = x.as_bytes();
+                while let Some(i) = rest.iter().position(|x| b"\\\t\n\r".contains(x)) {
+                    out.write_all(&rest[..i])?;
+                    out.write_all(match rest[i] {
+                        b'\\' => b"\\\\",
+                        b'\t' => b"\\t",
+                        b'\n' => b"\\n",
+                        _ => b"\\r",
+                    })?;
+                    rest = &rest[i + 1..];
+                }
+                out.write_all(rest)?;
+            }
+            Cell::Date(x) => write!(out, "{}", x.format("%Y-%m-%d"))?,
+            Cell::Int(x) => write!(out, "{x}")?,
+        }
+    }
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Writes a row as a field count followed by each field's length and value, with a length of -1
+/// for nulls. Text is UTF-8, which is also how enums and `char` are sent, and dates are days
+/// since 2000-01-01.
+pub fn write_binary(out: &mut impl Write, cells: &[Cell]) -> Result<()> {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1).expect("valid date");
+    out.write_all(&(cells.len() as i16).to_be_bytes())?;
+    for cell in cells {
+        match cell {
+            Cell::Null => out.write_all(&(-1i32).to_be_bytes())?,
+            Cell::Text(x) => {
+                out.write_all(&(x.len() as i32).to_be_bytes())?;
+                out.write_all(x.as_bytes())?;
+            }
+            Cell::Date(x) => {
+                let days = (*x - epoch).num_days() as i32;
+                out.write_all(&4i32.to_be_bytes())?;
+                out.write_all(&days.to_be_bytes())?;
+            }
+            Cell::Int(x) => {
+                out.write_all(&4i32.to_be_bytes())?;
+                out.write_all(&x.to_be_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Keeps sole traders' personal names out of the output, for consumers that mustn't receive
+//! them. Records are redacted before they reach any writer, so every format agrees.
+
+use std::{fs, path::PathBuf, str::FromStr};
+
+use anyhow::{bail, ensure, Context, Error, Result};
+use clap::Args;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::model::{ABNRecord, EntityName};
+
+#[derive(Debug, Default, Args)]
+pub struct PrivacyArgs {
+    /// Remove individuals' names (drop), or replace them with a keyed hash (hash) that's the same
+    /// for the same name, so records can still be linked
+    #[arg(long)]
+    pub redact_individuals: Option<Redaction>,
+    /// File with the secret key for --redact-individuals hash, at least 16 bytes
+    #[arg(long)]
+    pub hash_key_file: Option<PathBuf>,
+    /// Keep the business and trading names of individuals, which often include their name
+    #[arg(long)]
+    pub keep_individual_business_names: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redaction {
+    Drop,
+    Hash,
+}
+
+impl FromStr for Redaction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "drop" => Ok(Self::Drop),
+            "hash" => Ok(Self::Hash),
+            _ => bail!("unknown redaction, expected drop or hash"),
+        }
+    }
+}
+
+/// Redacts individuals' names as set up by `PrivacyArgs`, or does nothing if redaction is off.
+pub struct Redactor {
+    redaction: Option<Redaction>,
+    key: Option<Hmac<Sha256>>,
+    keep_business_names: bool,
+}
+
+impl Redactor {
+    pub fn new(args: &PrivacyArgs) -> Result<Self> {
+        let key = match (args.redact_individuals, &args.hash_key_file) {
+            (Some(Redaction::Hash), Some(path)) => {
+                let key = fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+                let key = key.trim_ascii_end();
+                ensure!(key.len() >= 16, "hash key must be at least 16 bytes");
+                Some(Hmac::new_from_slice(key)?)
+            }
+            (Some(Redaction::Hash), None) => {
+                bail!("--redact-individuals hash needs --hash-key-file")
+            }
+            (_, Some(_)) => bail!("--hash-key-file is only used with --redact-individuals hash"),
+            (_, None) => None,
+        };
+        ensure!(
+            args.redact_individuals.is_some() || !args.keep_individual_business_names,
+            "--keep-individual-business-names is only used with --redact-individuals"
+        );
+
+        Ok(Self {
+            redaction: args.redact_individuals,
+            key,
+            keep_business_names: args.keep_individual_business_names,
+        })
+    }
+
+    pub fn apply(&self, record: &mut ABNRecord) {
+        let Some(redaction) = self.redaction else {
+            return;
+        };
+        let EntityName::Individual { .. } = &record.entity_name else {
+            return;
+        };
+
+        let hash = match redaction {
+            Redaction::Drop => None,
+            Redaction::Hash => Some(self.hash(&record.entity_name)),
+        };
+        record.entity_name = EntityName::Redacted { hash };
+        if !self.keep_business_names {
+            record.other_names.clear();
+        }
+    }
+
+    /// Hex HMAC-SHA256 of the given and family names, hashed separately so different names
+    /// can't collide by moving words between them. Titles are left out since they come and go.
+    fn hash(&self, name: &EntityName) -> String {
+        let EntityName::Individual {
+            title: _,
+            given,
+            given_2,
+            family,
+        } = name
+        else {
+            unreachable!("only individuals are hashed");
+        };
+
+        let mut mac = self.key.clone().expect("hash redaction has a key");
+        for part in [given.as_deref(), given_2.as_deref(), Some(family.as_str())] {
+            // a length prefix tells an empty part from a missing one
+            match part {
+                Some(x) => {
+                    mac.update(&(x.len() as u64 + 1).to_le_bytes());
+                    mac.update(x.as_bytes());
+                }
+                None => mac.update(&0u64.to_le_bytes()),
+            }
+        }
+
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|x| format!("{x:02x}"))
+            .collect()
+    }
+}
@@ -0,0 +1,233 @@
+//! Progress of a conversion on stderr: a status line that redraws itself on a terminal, and
+//! optionally a JSON line every so often for log collectors.
+
+use std::{
+    io::{self, IsTerminal, Read, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use clap::Args;
+use serde_json::{json, Value};
+
+#[derive(Debug, Default, Args)]
+pub struct ProgressArgs {
+    /// Write a JSON progress line to stderr every this many seconds, for log collectors
+    #[arg(long, value_name = "SECONDS")]
+    pub progress_log: Option<u64>,
+}
+
+/// How far through the input files a conversion is.
+pub struct Progress {
+    /// Redraw a status line, which is only done on a terminal
+    display: bool,
+    /// Print each file's name as it's started, when there's no status line
+    names: bool,
+    log_interval: Option<Duration>,
+    started: Instant,
+    last_draw: Option<Instant>,
+    last_log: Instant,
+    file: String,
+    read: Counter,
+    /// Bytes converted before a resumed conversion started
+    skipped: u64,
+    total: u64,
+    records: u64,
+    errors: u64,
+    output: Box<dyn Write + Send>,
+}
+
+impl Progress {
+    /// Progress over input files totalling `total` bytes.
+    pub fn new(args: &ProgressArgs, total: u64) -> Self {
+        Self::writing_to(io::stderr(), io::stderr().is_terminal(), args, total)
+    }
+
+    /// Progress written to `output` instead of stderr, with a status line if it's a terminal.
+    pub fn writing_to(
+        output: impl Write + Send + 'static,
+        terminal: bool,
+        args: &ProgressArgs,
+        total: u64,
+    ) -> Self {
+        Self {
+            display: terminal,
+            names: !terminal,
+            log_interval: args.progress_log.map(Duration::from_secs),
+            total,
+            output: Box::new(output),
+            ..Self::hidden()
+        }
+    }
+
+    /// Progress that's counted without a status line or log.
+    pub fn hidden() -> Self {
+        let now = Instant::now();
+        Self {
+            display: false,
+            names: false,
+            log_interval: None,
+            started: now,
+            last_draw: None,
+            last_log: now,
+            file: String::new(),
+            read: Counter::default(),
+            skipped: 0,
+            total: 0,
+            records: 0,
+            errors: 0,
+            output: Box::new(io::sink()),
+        }
+    }
+
+    /// Counts the bytes read from input files, which can be done on another thread.
+    pub fn counter(&self) -> Counter {
+        self.read.clone()
+    }
+
+    /// Counts bytes converted before resuming, which are left out of the rate.
+    pub fn skip(&mut self, bytes: u64) {
+        self.read.0.fetch_add(bytes, Ordering::Relaxed);
+        self.skipped += bytes;
+    }
+
+    /// Starts writing records from the next file.
+    pub fn start_file(&mut self, name: &str) {
+        self.file = name.into();
+        if self.names {
+            let _ = writeln!(self.output, "{name}");
+        }
+    }
+
+    /// Counts a chunk of parsed records, and shows progress if it's due.
+    pub fn records(&mut self, records: u64) {
+        self.records += records;
+        let now = Instant::now();
+        if self.display
+            && self
+                .last_draw
+                .is_none_or(|x| now - x >= Duration::from_millis(200))
+        {
+            let line = self.line();
+            let _ = write!(self.output, "\r\x1b[K{line}");
+            self.last_draw = Some(now);
+        }
+        if self.log_interval.is_some_and(|x| now - self.last_log >= x) {
+            self.log();
+            self.last_log = now;
+        }
+    }
+
+    /// Prints a record that couldn't be parsed, above the status line.
+    pub fn error(&mut self, error: &anyhow::Error) {
+        self.errors += 1;
+        self.clear();
+        let _ = writeln!(self.output, "{error}");
+    }
+
+    /// Leaves the final status on its own line, and logs it.
+    pub fn finish(&mut self) {
+        if self.display {
+            let line = self.line();
+            let _ = writeln!(self.output, "\r\x1b[K{line}");
+            self.last_draw = None;
+        }
+        if self.log_interval.is_some() {
+            self.log();
+        }
+    }
+
+    /// The machine-readable progress, as logged by `--progress-log`.
+    pub fn status(&self) -> Value {
+        let elapsed = self.started.elapsed();
+        json!({
+            "file": self.file,
+            "records": self.records,
+            "errors": self.errors,
+            "bytes": self.bytes(),
+            "total_bytes": self.total,
+            "records_per_second": (self.records as f64 / elapsed.as_secs_f64()) as u64,
+            "elapsed_seconds": elapsed.as_secs(),
+            "eta_seconds": self.eta().map(|x| x.as_secs()),
+        })
+    }
+
+    fn bytes(&self) -> u64 {
+        self.read.0.load(Ordering::Relaxed)
+    }
+
+    fn eta(&self) -> Option<Duration> {
+        let bytes = self.bytes();
+        let read = bytes - self.skipped;
+        if read == 0 || bytes > self.total {
+            return None;
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        Some(Duration::from_secs_f64(
+            elapsed * (self.total - bytes) as f64 / read as f64,
+        ))
+    }
+
+    fn line(&self) -> String {
+        const MB: u64 = 1 << 20;
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let eta = match self.eta() {
+            Some(x) => format!("{}m{:02}s", x.as_secs() / 60, x.as_secs() % 60),
+            None => "?".into(),
+        };
+        format!(
+            "{}  {} records  {:.0}/s  {}/{} MB  ETA {eta}  {} errors",
+            self.file,
+            self.records,
+            self.records as f64 / elapsed,
+            self.bytes() / MB,
+            self.total / MB,
+            self.errors,
+        )
+    }
+
+    /// Clears the status line so the next line is printed above it, and it's redrawn after.
+    fn clear(&mut self) {
+        if self.display && self.last_draw.is_some() {
+            let _ = write!(self.output, "\r\x1b[K");
+            self.last_draw = None;
+        }
+    }
+
+    /// Prints the status as JSON, above the status line.
+    fn log(&mut self) {
+        self.clear();
+        let status = self.status();
+        let _ = writeln!(self.output, "{status}");
+    }
+}
+
+/// Bytes read so far from all input files.
+#[derive(Debug, Default, Clone)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    pub fn wrap<R: Read>(&self, inner: R) -> Counted<R> {
+        Counted {
+            inner,
+            read: self.clone(),
+        }
+    }
+}
+
+/// A reader that adds the bytes read through it to a `Counter`.
+pub struct Counted<R> {
+    inner: R,
+    read: Counter,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read.0.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
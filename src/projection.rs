@@ -0,0 +1,192 @@
+use std::{collections::HashSet, str::FromStr};
+
+use anyhow::{bail, ensure, Error, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The fields a record can have, and the fields inside each of them.
+const FIELDS: &[(&str, &[&str])] = &[
+    ("abn", &[]),
+    ("status", &[]),
+    ("status_since", &[]),
+    ("last_updated", &[]),
+    (
+        "entity_name",
+        &[
+            "type",
+            "title",
+            "given",
+            "given_2",
+            "family",
+            "name",
+            "hash",
+            "display_name",
+        ],
+    ),
+    ("entity_type", &[]),
+    ("trust", &["name", "kind"]),
+    ("other_names", &["name", "kind"]),
+    ("business_names", &[]),
+    ("trade_names", &[]),
+    ("postcode", &[]),
+    ("state", &[]),
+    ("asic_number", &[]),
+    ("gst_status", &[]),
+    ("gst_status_since", &[]),
+    ("match_key", &[]),
+];
+
+/// Fields only written with `--legacy-names`, in place of `other_names`.
+const LEGACY_FIELDS: &[&str] = &["business_names", "trade_names"];
+
+/// Selects and renames fields of the output, e.g. `abn,entity_name.family:surname,state`.
+///
+/// Nested fields keep their nesting unless renamed, and missing fields are written as null so
+/// every line has the same keys. Fields inside lists, like `other_names.name`, give a list.
+#[derive(Debug, Clone)]
+pub struct Projection {
+    fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    path: Vec<String>,
+    rename: Option<String>,
+}
+
+impl Projection {
+    /// Whether any selected field is this one or inside it, e.g. `["entity_name", "name"]`.
+    pub fn includes(&self, path: &[&str]) -> bool {
+        self.fields
+            .iter()
+            .any(|x| x.path.len() >= path.len() && x.path.iter().zip(path).all(|(a, b)| a == b))
+    }
+
+    /// Checks the selected names are the ones written: `business_names` and `trade_names` with
+    /// `--legacy-names`, and `other_names` without it.
+    pub fn check_names(&self, legacy_names: bool) -> Result<()> {
+        for field in &self.fields {
+            let name = field.path[0].as_str();
+            if LEGACY_FIELDS.contains(&name) {
+                ensure!(legacy_names, "{name} is only written with --legacy-names");
+            } else if name == "other_names" {
+                ensure!(!legacy_names, "{name} isn't written with --legacy-names");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, record: &impl Serialize) -> Result<Value> {
+        let record = serde_json::to_value(record)?;
+        let mut output = Map::new();
+        for field in &self.fields {
+            let value = get(&record, &field.path);
+            match &field.rename {
+                Some(name) => {
+                    output.insert(name.clone(), value);
+                }
+                None => insert(&mut output, &field.path, value),
+            }
+        }
+
+        Ok(Value::Object(output))
+    }
+
+    /// Flat names for the selected fields, e.g. for a CSV header: the new name if renamed, or
+    /// the path joined with dots.
+    pub fn columns(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .map(|x| x.rename.clone().unwrap_or_else(|| x.path.join(".")))
+            .collect()
+    }
+
+    /// The selected fields in order, matching `columns`.
+    pub fn row(&self, record: &impl Serialize) -> Result<Vec<Value>> {
+        let record = serde_json::to_value(record)?;
+        Ok(self.fields.iter().map(|x| get(&record, &x.path)).collect())
+    }
+}
+
+/// Follows a path into a value, going into each element of lists so `other_names.name` gives a
+/// list of names.
+fn get(value: &Value, path: &[String]) -> Value {
+    match (value, path) {
+        (_, []) => value.clone(),
+        (Value::Object(x), [key, rest @ ..]) => x.get(key).map_or(Value::Null, |x| get(x, rest)),
+        (Value::Array(x), _) => Value::Array(x.iter().map(|x| get(x, path)).collect()),
+        _ => Value::Null,
+    }
+}
+
+fn insert(output: &mut Map<String, Value>, path: &[String], value: Value) {
+    match path {
+        [key] => {
+            output.insert(key.clone(), value);
+        }
+        [key, rest @ ..] => {
+            let inner = output
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(inner) = inner {
+                insert(inner, rest, value);
+            }
+        }
+        [] => (),
+    }
+}
+
+impl FromStr for Projection {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let fields = s
+            .split(',')
+            .map(|x| {
+                let (path, rename) = match x.trim().split_once(':') {
+                    Some((path, rename)) => (path, Some(rename.trim().to_string())),
+                    None => (x.trim(), None),
+                };
+                let path: Vec<_> = path.split('.').map(|x| x.trim().to_string()).collect();
+                ensure!(path.iter().all(|x| !x.is_empty()), "empty field in {x:?}");
+                ensure!(
+                    rename.as_ref().is_none_or(|x| !x.is_empty()),
+                    "empty name in {x:?}"
+                );
+                let known = match &path[..] {
+                    [field] => FIELDS.iter().any(|x| x.0 == field),
+                    [field, inner] => FIELDS
+                        .iter()
+                        .any(|x| x.0 == field && x.1.contains(&&**inner)),
+                    _ => false,
+                };
+                if !known {
+                    bail!("unknown field: {}", path.join("."));
+                }
+                Ok(Field { path, rename })
+            })
+            .collect::<Result<_>>()?;
+        let projection = Self { fields };
+
+        // each name can only be written once, whether it's a rename or a field's own
+        let mut names = HashSet::new();
+        for name in projection.columns() {
+            ensure!(
+                names.insert(name.clone()),
+                "{name} is selected more than once"
+            );
+        }
+        for field in &projection.fields {
+            if let Some(name) = &field.rename {
+                ensure!(
+                    !projection
+                        .fields
+                        .iter()
+                        .any(|x| x.rename.is_none() && x.path[0] == *name),
+                    "{name} is selected more than once"
+                );
+            }
+        }
+        Ok(projection)
+    }
+}
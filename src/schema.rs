@@ -0,0 +1,124 @@
+//! A JSON Schema of the records convert writes, so consumers can validate what they load and
+//! see when it changes.
+
+use std::io::{self, Write};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::model::{EntityType, Status, TrustKind, SCHEMA_VERSION};
+
+/// The schema of an `ABNRecord` as written by convert, including the fields added by options
+/// like `--match-key`. It's versioned by `SCHEMA_VERSION`.
+pub fn schema() -> Value {
+    let date = json!({ "type": "string", "format": "date" });
+    let string = json!({ "type": "string" });
+    // business_names and trade_names replace other_names with --legacy-names
+    let strings = json!({ "type": "array", "items": { "type": "string" } });
+    let entity_types: Vec<_> = EntityType::ALL
+        .iter()
+        .map(|x| json!({ "const": format!("{x:?}"), "description": x.description() }))
+        .collect();
+    let statuses = Status::ALL.map(Status::label);
+    let trust_kinds = TrustKind::ALL.map(TrustKind::label);
+    // each kind of name is tagged by its type, and can have a display name added
+    let name = |kind: &str, mut properties: Value, required: &[&str]| {
+        properties["type"] = json!({ "const": kind });
+        properties["display_name"] = json!({
+            "type": "string",
+            "description": "The name for showing to people, with --display-name"
+        });
+        let required: Vec<_> = ["type"].iter().chain(required).collect();
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false
+        })
+    };
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "ABNRecord",
+        "description": "A record of the ABN bulk extract, as written by simple-abns convert",
+        "version": SCHEMA_VERSION,
+        "type": "object",
+        "properties": {
+            "abn": { "type": "string", "pattern": "^[0-9]{11}$" },
+            "status": { "$ref": "#/$defs/Status" },
+            "status_since": date,
+            "last_updated": date,
+            "entity_name": { "$ref": "#/$defs/EntityName" },
+            "entity_type": { "$ref": "#/$defs/EntityType" },
+            "trust": {
+                "type": "object",
+                "properties": {
+                    "name": string,
+                    "kind": { "enum": trust_kinds }
+                },
+                "required": ["name", "kind"],
+                "additionalProperties": false
+            },
+            "other_names": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": string,
+                        "kind": {
+                            "type": "string",
+                            "description": "BN for business names, TRD for trading names, OTN for old trading names, or another code the ABR uses"
+                        }
+                    },
+                    "required": ["name", "kind"],
+                    "additionalProperties": false
+                }
+            },
+            "business_names": strings,
+            "trade_names": strings,
+            "postcode": string,
+            "state": string,
+            "asic_number": string,
+            "gst_status": { "$ref": "#/$defs/Status" },
+            "gst_status_since": date,
+            "match_key": {
+                "type": "string",
+                "description": "The entity name normalised for matching, with --match-key"
+            }
+        },
+        "required": ["abn", "status", "status_since", "last_updated", "entity_name", "entity_type"],
+        "additionalProperties": false,
+        "$defs": {
+            "Status": { "enum": statuses },
+            "EntityType": { "oneOf": entity_types },
+            "EntityName": {
+                "oneOf": [
+                    name(
+                        "Individual",
+                        json!({ "title": string, "given": string, "given_2": string, "family": string }),
+                        &["family"]
+                    ),
+                    name("NonIndividual", json!({ "name": string }), &["name"]),
+                    name(
+                        "Redacted",
+                        json!({
+                            "hash": {
+                                "type": "string",
+                                "description": "A keyed hash of the individual's name, with --redact-individuals hash"
+                            }
+                        }),
+                        &[]
+                    )
+                ]
+            }
+        }
+    })
+}
+
+/// Prints the schema, for the schema command.
+pub fn print() -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, &schema())?;
+    writeln!(stdout)?;
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::{
+    fmt,
+    io::Cursor,
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+};
+
+use anyhow::{anyhow, Error, Result};
+use clap::Args;
+use percent_encoding::percent_decode_str;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    filter::Filter,
+    index::{self, Dataset, Index},
+    model::Status,
+};
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Converted dataset that has been indexed with the index command
+    data: PathBuf,
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    addr: SocketAddr,
+    /// Number of requests to handle at once
+    #[arg(long, default_value_t = 4)]
+    threads: usize,
+}
+
+/// A request that can't be answered as asked, responded to with a 400 rather than a 500.
+#[derive(Debug)]
+pub struct BadRequest(String);
+
+impl fmt::Display for BadRequest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for BadRequest {}
+
+fn bad_request(e: impl fmt::Display) -> Error {
+    BadRequest(e.to_string()).into()
+}
+
+pub fn run(args: ServeArgs) -> Result<()> {
+    let index = Index::load(&index::index_path(&args.data))?;
+    let server = Server::http(args.addr).map_err(|e| anyhow!("failed to listen: {e}"))?;
+    eprintln!("listening on http://{}", args.addr);
+    let datasets = (0..args.threads.max(1))
+        .map(|_| Dataset::open(&args.data))
+        .collect::<Result<_>>()?;
+    serve(&server, &AtomicBool::new(false), &index, datasets)
+}
+
+/// Answers requests with a thread for each dataset, until `stop` is called with the same
+/// `stopping` or a thread panics.
+pub fn serve(
+    server: &Server,
+    stopping: &AtomicBool,
+    index: &Index,
+    datasets: Vec<Dataset>,
+) -> Result<()> {
+    thread::scope(|s| {
+        let workers: Vec<_> = datasets
+            .into_iter()
+            .map(|mut dataset| {
+                s.spawn(move || loop {
+                    let request = match server.recv() {
+                        Ok(x) => x,
+                        Err(_) if stopping.load(Ordering::SeqCst) => {
+                            // each unblock wakes one thread, so it's passed on to the next
+                            server.unblock();
+                            return;
+                        }
+                        Err(e) => {
+                            eprintln!("failed to receive a request: {e}");
+                            continue;
+                        }
+                    };
+                    let response = match handle(index, &mut dataset, &request) {
+                        Ok(Some(body)) => json(200, body),
+                        Ok(None) => json(404, r#"{"error":"not found"}"#.into()),
+                        Err(e) => {
+                            let status = match e.is::<BadRequest>() {
+                                true => 400,
+                                false => {
+                                    eprintln!("failed to answer {}: {e:#}", request.url());
+                                    500
+                                }
+                            };
+                            let body = serde_json::json!({ "error": format!("{e:#}") });
+                            json(status, body.to_string())
+                        }
+                    };
+                    if let Err(e) = request.respond(response) {
+                        eprintln!("failed to respond: {e}");
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().map_err(|_| anyhow!("worker panicked"))?;
+        }
+        Ok(())
+    })
+}
+
+/// Makes `serve` return once its threads have answered the requests they're handling, e.g. from
+/// another thread on shutdown.
+pub fn stop(server: &Server, stopping: &AtomicBool) {
+    stopping.store(true, Ordering::SeqCst);
+    server.unblock();
+}
+
+fn handle(index: &Index, dataset: &mut Dataset, request: &Request) -> Result<Option<String>> {
+    if request.method() != &Method::Get {
+        return Ok(None);
+    }
+
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let query: Vec<_> = form_urlencoded::parse(query.as_bytes()).collect();
+    let param = |key: &str| {
+        query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_ref())
+    };
+
+    // abns are often written with spaces, which arrive encoded
+    let segments = path
+        .trim_matches('/')
+        .split('/')
+        .map(|x| percent_decode_str(x).decode_utf8().map_err(bad_request))
+        .collect::<Result<Vec<_>>>()?;
+    let segments: Vec<_> = segments.iter().map(|x| x.as_ref()).collect();
+    match segments[..] {
+        ["abn", abn] => match index.abn(abn) {
+            Some(id) => Ok(Some(serde_json::to_string(
+                &dataset.get(index.offset(id))?,
+            )?)),
+            None => Ok(None),
+        },
+        ["acn", acn] => {
+            let mut records = index
+                .acn(acn)
+                .into_iter()
+                .map(|id| dataset.get(index.offset(id)))
+                .collect::<Result<Vec<_>>>()?;
+            // an acn can outlive the abn it was first registered with
+            records.sort_by_key(|x| x.status != Status::Active);
+            match records.into_iter().next() {
+                Some(record) => Ok(Some(serde_json::to_string(&record)?)),
+                None => Ok(None),
+            }
+        }
+        ["search"] => {
+            let q = param("q").ok_or_else(|| bad_request("missing q"))?;
+            let filter = Filter {
+                status: parse(param("status"))?,
+                entity_type: parse(param("type"))?.into_iter().collect(),
+                state: param("state").map(Into::into).into_iter().collect(),
+                postcode: parse(param("postcode"))?,
+                ..Default::default()
+            };
+            let limit: usize = parse(param("limit"))?.unwrap_or(20);
+            let hits = index.search(dataset, q, &filter, limit.min(1000))?;
+            Ok(Some(serde_json::to_string(&hits)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Parses a query parameter if it was given, as a bad request if it's invalid.
+fn parse<T: FromStr<Err: fmt::Display>>(value: Option<&str>) -> Result<Option<T>> {
+    value.map(str::parse).transpose().map_err(bad_request)
+}
+
+fn json(status: u16, body: String) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+}
@@ -0,0 +1,234 @@
+//! A minimal XML reader for single ABR records that borrows everything it can from the input.
+//!
+//! Only the parts of XML the extract actually uses are supported: elements, attributes, text,
+//! character and predefined entity references, comments, CDATA and processing instructions.
+
+use std::borrow::Cow;
+
+use anyhow::{bail, Context, Result};
+
+#[derive(Debug, PartialEq)]
+pub enum Event<'a> {
+    Start {
+        name: &'a str,
+        attributes: Attributes<'a>,
+    },
+    End {
+        name: &'a str,
+    },
+    Text(Cow<'a, str>),
+}
+
+pub struct Reader<'a> {
+    input: &'a str,
+    pos: usize,
+    // set after an empty element like <GST />, which is reported as a start and an end
+    pending_end: Option<&'a str>,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            pending_end: None,
+        }
+    }
+
+    pub fn read_event(&mut self) -> Result<Option<Event<'a>>> {
+        if let Some(name) = self.pending_end.take() {
+            return Ok(Some(Event::End { name }));
+        }
+
+        loop {
+            let rest = &self.input[self.pos..];
+            if rest.is_empty() {
+                return Ok(None);
+            }
+
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                let text = &rest[..end];
+                // whitespace between elements isn't content
+                if text.bytes().all(|x| x.is_ascii_whitespace()) {
+                    continue;
+                }
+                return Ok(Some(Event::Text(unescape(text)?)));
+            }
+
+            if let Some(rest) = rest.strip_prefix("<?") {
+                self.pos += 2
+                    + rest
+                        .find("?>")
+                        .context("unterminated processing instruction")?
+                    + 2;
+            } else if let Some(rest) = rest.strip_prefix("<!--") {
+                self.pos += 4 + rest.find("-->").context("unterminated comment")? + 3;
+            } else if let Some(rest) = rest.strip_prefix("<![CDATA[") {
+                let end = rest.find("]]>").context("unterminated cdata")?;
+                self.pos += 9 + end + 3;
+                return Ok(Some(Event::Text(Cow::Borrowed(&rest[..end]))));
+            } else if rest.starts_with("<!") {
+                bail!("unsupported markup declaration");
+            } else if let Some(rest) = rest.strip_prefix("</") {
+                let end = rest.find('>').context("unterminated end tag")?;
+                self.pos += 2 + end + 1;
+                let name = rest[..end].trim_end();
+                check_name(name)?;
+                return Ok(Some(Event::End { name }));
+            } else {
+                return self.start().map(Some);
+            }
+        }
+    }
+
+    fn start(&mut self) -> Result<Event<'a>> {
+        let tag = &self.input[self.pos + 1..];
+        let name_end = tag
+            .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+            .context("unterminated start tag")?;
+        let name = &tag[..name_end];
+        check_name(name)?;
+
+        // find the end of the tag, skipping over quoted attribute values
+        let bytes = tag.as_bytes();
+        let mut i = name_end;
+        let mut quote = None;
+        let end = loop {
+            match (bytes.get(i), quote) {
+                (None, _) => bail!("unterminated start tag"),
+                (Some(&c), Some(q)) if c == q => quote = None,
+                (Some(_), Some(_)) => (),
+                (Some(&c @ (b'"' | b'\'')), None) => quote = Some(c),
+                (Some(b'>'), None) => break i,
+                (Some(_), None) => (),
+            }
+            i += 1;
+        };
+
+        self.pos += 1 + end + 1;
+        let (attributes, empty) = match tag[name_end..end].strip_suffix('/') {
+            Some(x) => (x, true),
+            None => (&tag[name_end..end], false),
+        };
+        if empty {
+            self.pending_end = Some(name);
+        }
+
+        Ok(Event::Start {
+            name,
+            attributes: Attributes(attributes),
+        })
+    }
+}
+
+/// The unparsed attributes of a start tag, which are read lazily.
+#[derive(Debug, PartialEq)]
+pub struct Attributes<'a>(&'a str);
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = Result<(&'a str, Cow<'a, str>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.0.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+
+        let attribute = (|| {
+            let (key, rest) = rest.split_once('=').context("attribute without a value")?;
+            let key = key.trim_end();
+            check_name(key)?;
+            let rest = rest.trim_start();
+            let quote = rest.chars().next().context("attribute without a value")?;
+            if quote != '"' && quote != '\'' {
+                bail!("unquoted attribute value");
+            }
+            let end = rest[1..]
+                .find(quote)
+                .context("unterminated attribute value")?;
+            let value = unescape(&rest[1..1 + end])?;
+            Ok(((key, value), &rest[1 + end + 1..]))
+        })();
+
+        match attribute {
+            Ok((x, rest)) => {
+                self.0 = rest;
+                Some(Ok(x))
+            }
+            Err(e) => {
+                self.0 = "";
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(|c: char| c.is_ascii_whitespace() || "<>\"'=/&".contains(c))
+    {
+        bail!("invalid name: {name:?}");
+    }
+    Ok(())
+}
+
+/// Replaces entity and character references, only allocating if there are any.
+pub fn unescape(text: &str) -> Result<Cow<'_, str>> {
+    if !text.contains('&') {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        let end = rest[start..].find(';').context("unterminated reference")?;
+        let reference = &rest[start + 1..start + end];
+        match reference {
+            "lt" => output.push('<'),
+            "gt" => output.push('>'),
+            "amp" => output.push('&'),
+            "apos" => output.push('\''),
+            "quot" => output.push('"'),
+            _ => {
+                let code = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => reference.strip_prefix('#').and_then(|x| x.parse().ok()),
+                };
+                match code.and_then(char::from_u32) {
+                    Some(c) if c != '\0' => output.push(c),
+                    _ => bail!("unknown reference: &{reference};"),
+                }
+            }
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(Cow::Owned(output))
+}
+
+/// Escapes text for use in element content or a quoted attribute value, only allocating if
+/// there's anything to escape. Line breaks are escaped too, so records stay on one line.
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'', '\n', '\r']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut output = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&apos;"),
+            '\n' => output.push_str("&#10;"),
+            '\r' => output.push_str("&#13;"),
+            c => output.push(c),
+        }
+    }
+
+    Cow::Owned(output)
+}
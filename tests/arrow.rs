@@ -0,0 +1,148 @@
+mod common;
+
+use std::{fs::File, sync::Arc};
+
+use arrow_array::{
+    cast::AsArray,
+    types::{Date32Type, Int8Type},
+    Array, RecordBatch, StringArray,
+};
+use arrow_ipc::reader::{FileReader, StreamReader};
+use arrow_schema::DataType;
+use chrono::NaiveDate;
+use common::TempDir;
+use simple_abns::{
+    arrow::{schema, ArrowWriter},
+    convert::{convert, ConvertArgs},
+    model::{ABNRecord, EntityName, OtherName, Trust, SCHEMA_VERSION},
+};
+
+fn string(array: &dyn Array, row: usize) -> Option<String> {
+    let array = match array.data_type() {
+        DataType::Dictionary(..) => {
+            let array = array.as_dictionary::<Int8Type>();
+            let key = array.keys();
+            if key.is_null(row) {
+                return None;
+            }
+            let values: &StringArray = array.values().as_string();
+            return Some(values.value(key.value(row) as usize).to_string());
+        }
+        _ => array.as_string::<i32>(),
+    };
+    array.is_valid(row).then(|| array.value(row).to_string())
+}
+
+fn date(array: &dyn Array, row: usize) -> Option<NaiveDate> {
+    let array = array.as_primitive::<Date32Type>();
+    array
+        .is_valid(row)
+        .then(|| Date32Type::to_naive_date(array.value(row)))
+}
+
+fn parse<T: serde::de::DeserializeOwned>(value: String) -> T {
+    serde_json::from_value(value.into()).unwrap()
+}
+
+/// Reads records back out of a batch, to compare with what was written.
+fn records(batch: &RecordBatch) -> Vec<ABNRecord> {
+    let column = |name| batch.column_by_name(name).unwrap().as_ref();
+    let entity_name = column("entity_name").as_struct();
+    let name_part = |name, row| string(entity_name.column_by_name(name).unwrap(), row);
+    let trust = column("trust").as_struct();
+    let other_names = column("other_names").as_list::<i32>();
+
+    (0..batch.num_rows())
+        .map(|row| ABNRecord {
+            abn: string(column("abn"), row).unwrap(),
+            status: parse(string(column("status"), row).unwrap()),
+            status_since: date(column("status_since"), row).unwrap(),
+            last_updated: date(column("last_updated"), row).unwrap(),
+            entity_name: match name_part("type", row).unwrap().as_str() {
+                "Individual" => EntityName::Individual {
+                    title: name_part("title", row),
+                    given: name_part("given", row),
+                    given_2: name_part("given_2", row),
+                    family: name_part("family", row).unwrap(),
+                },
+                "NonIndividual" => EntityName::NonIndividual {
+                    name: name_part("name", row).unwrap(),
+                },
+                _ => EntityName::Redacted {
+                    hash: name_part("hash", row),
+                },
+            },
+            entity_type: parse(string(column("entity_type"), row).unwrap()),
+            trust: trust.is_valid(row).then(|| Trust {
+                name: string(trust.column(0), row).unwrap(),
+                kind: parse(string(trust.column(1), row).unwrap()),
+            }),
+            other_names: {
+                let names = other_names.value(row);
+                let names = names.as_struct();
+                (0..names.len())
+                    .map(|i| OtherName {
+                        name: string(names.column(0), i).unwrap(),
+                        kind: string(names.column(1), i).unwrap().into(),
+                    })
+                    .collect()
+            },
+            postcode: string(column("postcode"), row),
+            state: string(column("state"), row),
+            asic_number: string(column("asic_number"), row),
+            gst_status: string(column("gst_status"), row).map(parse),
+            gst_status_since: date(column("gst_status_since"), row),
+        })
+        .collect()
+}
+
+#[test]
+fn round_trip() {
+    let extract = common::extract(3000, 0);
+    let expected = common::records(&extract);
+
+    let dir = TempDir::new("arrow");
+    for name in ["out.arrow", "out.arrows"] {
+        let path = dir.join(name);
+        let mut output = ArrowWriter::create(&path, true, true).unwrap();
+        let args = ConvertArgs {
+            chunk_size: Some(1000),
+            format: simple_abns::output::Format::Arrow,
+            ..Default::default()
+        };
+        convert(&extract[..], &mut output, &args).unwrap();
+        let written = output.finish().unwrap();
+        assert_eq!(written.records, expected.len() as u64);
+
+        let file = File::open(&path).unwrap();
+        let batches: Vec<_> = if name.ends_with(".arrows") {
+            StreamReader::try_new(file, None)
+                .unwrap()
+                .map(|x| x.unwrap())
+                .collect()
+        } else {
+            FileReader::try_new(file, None)
+                .unwrap()
+                .map(|x| x.unwrap())
+                .collect()
+        };
+        // a batch for each chunk, header lines included
+        assert_eq!(batches.len(), 4, "{name}");
+        assert_eq!(batches[0].schema(), Arc::new(schema(true, true)));
+        let metadata = batches[0].schema().metadata().clone();
+        assert_eq!(metadata["simple_abns_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata["schema_version"], SCHEMA_VERSION.to_string());
+
+        let records: Vec<_> = batches.iter().flat_map(records).collect();
+        assert!(records == expected, "{name} didn't round trip");
+        let match_keys = batches[0].column_by_name("match_key").unwrap();
+        assert!(string(match_keys, 0).is_some_and(|x| !x.is_empty()));
+        let last = batches.last().unwrap();
+        let entity_names = last.column_by_name("entity_name").unwrap().as_struct();
+        let display_names = entity_names.column_by_name("display_name").unwrap();
+        assert_eq!(
+            string(display_names, last.num_rows() - 1),
+            Some(expected.last().unwrap().entity_name.display_name())
+        );
+    }
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+use common::{extract, TempDir};
+use flate2::read::MultiGzDecoder;
+use simple_abns::{
+    checkpoint::{Checkpoint, Fingerprint},
+    convert::{convert_inputs, ConvertArgs, Input},
+    output::{CompressionArgs, Writer},
+    progress::Progress,
+};
+
+fn read(path: &Path) -> Vec<u8> {
+    let file = File::open(path).unwrap();
+    let mut input: Box<dyn Read> = match path.extension().unwrap().to_str() {
+        Some("zst") => Box::new(zstd::Decoder::new(file).unwrap()),
+        Some("gz") => Box::new(MultiGzDecoder::new(file)),
+        _ => Box::new(file),
+    };
+    let mut output = Vec::new();
+    input.read_to_end(&mut output).unwrap();
+    output
+}
+
+fn convert(path: &Path, inputs: Vec<Input<&[u8]>>, output: &mut Writer) {
+    let args = ConvertArgs {
+        output: Some(path.into()),
+        chunk_size: Some(64),
+        ..Default::default()
+    };
+    let inputs = inputs.into_iter().map(Ok);
+    convert_inputs(inputs, output, &args, &mut Progress::hidden()).unwrap();
+}
+
+#[test]
+fn resume() {
+    let a = extract(1000, 1);
+    let b = extract(1000, 2);
+    // part way through a, at the end of a line
+    let cut = a
+        .iter()
+        .enumerate()
+        .filter(|x| *x.1 == b'\n')
+        .nth(500)
+        .unwrap()
+        .0
+        + 1;
+
+    let dir = TempDir::new("resume");
+    for name in ["out.jsonl", "out.jsonl.zst", "out.jsonl.gz"] {
+        let path = dir.join(name);
+        let compression = CompressionArgs::default();
+
+        let mut output = Writer::create(&dir.join(name), compression, 2, None).unwrap();
+        convert(
+            &path,
+            vec![Input::new("a", &a[..]), Input::new("b", &b[..])],
+            &mut output,
+        );
+        output.finish().unwrap();
+        let expected = read(&path);
+
+        // stop where a has been read up to the cut, then leave half a record after it
+        let mut output = Writer::create(&path, compression, 2, None).unwrap();
+        convert(&path, vec![Input::new("a", &a[..cut])], &mut output);
+        drop(output);
+        let checkpoint = Checkpoint::load(&Checkpoint::path(&path)).unwrap();
+        assert_eq!(checkpoint.input, "a");
+        assert_eq!(checkpoint.offset, cut as u64);
+        assert_eq!(checkpoint.line, 501);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"abn\":\"5").unwrap();
+        drop(file);
+
+        let mut output = Writer::append(&path, compression, 2, checkpoint.output).unwrap();
+        let rest = Input {
+            offset: checkpoint.offset,
+            line: checkpoint.line,
+            ..Input::new("a", &a[cut..])
+        };
+        convert(&path, vec![rest, Input::new("b", &b[..])], &mut output);
+        output.finish().unwrap();
+        assert!(read(&path) == expected, "{name} didn't resume");
+
+        let checkpoint = Checkpoint::load(&Checkpoint::path(&path)).unwrap();
+        assert_eq!(checkpoint.input, "b");
+        assert_eq!(checkpoint.offset, b.len() as u64);
+        assert_eq!(checkpoint.output.records, 2000);
+    }
+}
+
+#[test]
+fn short_output() {
+    let dir = TempDir::new("short");
+    let path = dir.join("out.jsonl");
+    fs::write(&path, "{}\n").unwrap();
+    let written = serde_json::from_str(r#"{"bytes":100,"records":10}"#).unwrap();
+    assert!(Writer::append(&path, CompressionArgs::default(), 1, written).is_err());
+}
+
+#[test]
+fn fingerprint() {
+    let dir = TempDir::new("fingerprint");
+    let inputs: Vec<_> = ["a.xml", "b.xml"]
+        .iter()
+        .map(|x| dir.join(x).to_str().unwrap().to_string())
+        .collect();
+    fs::write(&inputs[0], "<ABR>").unwrap();
+    let options = ("jsonl", Some("abn,state"), false);
+    let started = Fingerprint::new(&options, &inputs);
+    assert_eq!(started.inputs[0].bytes, Some(5));
+    assert!(started.inputs[0].modified.is_some());
+    assert_eq!(started.inputs[1].bytes, None);
+
+    // saved and loaded as it was
+    let path = dir.join("out.jsonl.checkpoint");
+    let checkpoint = Checkpoint {
+        fingerprint: started.clone(),
+        input: inputs[0].clone(),
+        offset: 5,
+        line: 1,
+        output: serde_json::from_str(r#"{"bytes":100,"records":10}"#).unwrap(),
+    };
+    checkpoint.save(&path).unwrap();
+    assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+    let started = checkpoint.fingerprint;
+
+    let error = |options: &(&str, Option<&str>, bool)| {
+        let current = Fingerprint::new(options, &inputs);
+        started.check(&current).err().map(|x| x.to_string())
+    };
+    assert_eq!(error(&options), None);
+    assert_eq!(
+        error(&("jsonl", Some("abn"), false)).unwrap(),
+        "the options that change the output aren't the same as when it was started"
+    );
+    fs::write(&inputs[1], "").unwrap();
+    assert_eq!(
+        error(&options).unwrap(),
+        format!("{} has changed since it was started", inputs[1])
+    );
+    fs::remove_file(&inputs[1]).unwrap();
+    // the same size, but written since
+    fs::write(&inputs[0], "<ABN>").unwrap();
+    let modified = started.inputs[0].modified.unwrap() + Duration::from_secs(1);
+    let file = File::options().write(true).open(&inputs[0]).unwrap();
+    file.set_modified(modified).unwrap();
+    assert_eq!(
+        error(&options).unwrap(),
+        format!("{} has changed since it was started", inputs[0])
+    );
+}
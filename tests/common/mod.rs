@@ -0,0 +1,82 @@
+//! Fixtures shared by the integration tests.
+
+// each test binary only uses some of these
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    io::BufRead,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use simple_abns::{
+    convert::{convert, ConvertArgs},
+    generate::{generate, GenerateOptions},
+    index::{index_path, Index},
+    model::ABNRecord,
+};
+
+/// A synthetic extract of `records` records.
+pub fn extract(records: usize, seed: u64) -> Vec<u8> {
+    let mut extract = Vec::new();
+    let options = GenerateOptions {
+        records,
+        seed,
+        ..Default::default()
+    };
+    generate(&options, &mut extract).unwrap();
+    extract
+}
+
+/// The records convert writes for an extract, with the default options.
+pub fn records(extract: &[u8]) -> Vec<ABNRecord> {
+    let mut json = Vec::new();
+    convert(extract, &mut json, &ConvertArgs::default()).unwrap();
+    json.lines()
+        .map(|x| serde_json::from_str(&x.unwrap()).unwrap())
+        .collect()
+}
+
+/// Writes records as a converted dataset in `dir` and indexes it, returning the dataset's path.
+pub fn indexed(dir: &TempDir, records: &[ABNRecord]) -> PathBuf {
+    let path = dir.join("data.jsonl");
+    let lines: String = records
+        .iter()
+        .map(|x| serde_json::to_string(x).unwrap() + "\n")
+        .collect();
+    fs::write(&path, lines).unwrap();
+    Index::build(&path)
+        .unwrap()
+        .save(&index_path(&path))
+        .unwrap();
+    path
+}
+
+/// A directory of its own for a test, removed with everything in it when it's dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("simple-abns-{name}-{}-{n}", process::id()));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
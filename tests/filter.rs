@@ -0,0 +1,157 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{extract, records};
+use simple_abns::{
+    filter::{Filter, GstStatus, PostcodeRange},
+    model::{ABNRecord, EntityCategory, Status},
+};
+
+fn record() -> ABNRecord {
+    let mut record = records(&extract(1, 0)).remove(0);
+    record.state = Some("VIC".into());
+    record.postcode = Some("3000".into());
+    record.status_since = date("2010-07-01");
+    record.last_updated = date("2024-11-27");
+    record.gst_status = Some(Status::Active);
+    record
+}
+
+fn date(x: &str) -> NaiveDate {
+    x.parse().unwrap()
+}
+
+#[test]
+fn states() {
+    let matches = |states: &[&str], record: &ABNRecord| {
+        let filter = Filter {
+            state: states.iter().map(|x| x.to_string()).collect(),
+            ..Default::default()
+        };
+        filter.matches(record)
+    };
+    let mut record = record();
+    for states in [&["VIC"][..], &["vic"], &[" Vic "], &["NSW", "vic"], &[]] {
+        assert!(matches(states, &record), "{states:?}");
+    }
+    assert!(!matches(&["NSW"], &record));
+    record.state = None;
+    assert!(!matches(&["VIC"], &record));
+}
+
+#[test]
+fn postcode_ranges() {
+    let range = |x: &str| x.parse::<PostcodeRange>();
+    let single = range("3000").unwrap();
+    assert_eq!((single.from, single.to), (3000, 3000));
+    let spaced = range(" 3000 - 3999 ").unwrap();
+    assert_eq!((spaced.from, spaced.to), (3000, 3999));
+
+    let range = range("3000-3999").unwrap();
+    for (postcode, inside) in [
+        (2999, false),
+        (3000, true),
+        (3500, true),
+        (3999, true),
+        (4000, false),
+    ] {
+        assert_eq!(range.contains(postcode), inside, "{postcode}");
+    }
+    for invalid in [
+        "3999-3000",
+        "abc",
+        "3000-",
+        "-3000",
+        "3000-3999-4000",
+        "70000",
+    ] {
+        assert!(invalid.parse::<PostcodeRange>().is_err(), "{invalid}");
+    }
+
+    let filter = Filter {
+        postcode: Some(range),
+        ..Default::default()
+    };
+    let mut record = record();
+    assert!(filter.matches(&record));
+    for postcode in [Some("4000"), Some("ABC"), None] {
+        record.postcode = postcode.map(Into::into);
+        assert!(!filter.matches(&record), "{postcode:?}");
+    }
+}
+
+#[test]
+fn date_ranges() {
+    let record = record();
+    // status since 2010-07-01, and last updated 2024-11-27, with bounds included
+    let status_since = [
+        (Some("2010-07-01"), None, true),
+        (Some("2010-07-02"), None, false),
+        (None, Some("2010-07-01"), true),
+        (None, Some("2010-06-30"), false),
+        (Some("2000-01-01"), Some("2020-01-01"), true),
+        (Some("2020-01-01"), Some("2000-01-01"), false),
+    ];
+    for (from, to, expected) in status_since {
+        let filter = Filter {
+            status_since_from: from.map(date),
+            status_since_to: to.map(date),
+            ..Default::default()
+        };
+        assert_eq!(filter.matches(&record), expected, "{from:?} {to:?}");
+    }
+    let last_updated = [
+        (Some("2024-11-27"), None, true),
+        (Some("2024-11-28"), None, false),
+        (None, Some("2024-11-27"), true),
+        (None, Some("2024-11-26"), false),
+        (Some("2024-01-01"), Some("2024-12-31"), true),
+    ];
+    for (from, to, expected) in last_updated {
+        let filter = Filter {
+            last_updated_from: from.map(date),
+            last_updated_to: to.map(date),
+            ..Default::default()
+        };
+        assert_eq!(filter.matches(&record), expected, "{from:?} {to:?}");
+    }
+}
+
+#[test]
+fn gst_statuses() {
+    let filter = |x: &str| Filter {
+        gst_status: Some(x.parse::<GstStatus>().unwrap()),
+        ..Default::default()
+    };
+    let mut record = record();
+    assert!(filter("active").matches(&record));
+    assert!(filter("ACT").matches(&record));
+    assert!(!filter("cancelled").matches(&record));
+    assert!(!filter("none").matches(&record));
+
+    // none means never registered, rather than any status
+    record.gst_status = None;
+    assert!(filter("none").matches(&record));
+    assert!(filter("NONE").matches(&record));
+    assert!(!filter("active").matches(&record));
+    assert!("never".parse::<GstStatus>().is_err());
+}
+
+#[test]
+fn values_in_any_case() {
+    let record = record();
+    for status in ["active", "ACTIVE", "Act"] {
+        let filter = Filter {
+            status: Some(status.parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&record), "{status}");
+    }
+    for name in ["company", "Company", "COMPANY"] {
+        assert_eq!(
+            name.parse::<EntityCategory>().unwrap(),
+            EntityCategory::Company
+        );
+    }
+    assert!("companies".parse::<EntityCategory>().is_err());
+}
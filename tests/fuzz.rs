@@ -0,0 +1,192 @@
+//! Property tests that throw arbitrary and mutated records at `parse_record`.
+//!
+//! The parser must never panic or hang, and anything it accepts must survive a round trip
+//! through JSON, and back through XML with `emit_record`, unchanged. Each case runs in a forked
+//! process with a timeout so a hang fails the test instead of stalling it. Set `PROPTEST_CASES`
+//! to run more cases than the default.
+
+use std::{fs, path::Path, sync::OnceLock};
+
+use proptest::{prelude::*, test_runner::FileFailurePersistence};
+use simple_abns::{
+    emit::emit_record,
+    generate::{GenerateOptions, Generator},
+    model::ABNRecord,
+    parser::parse_record,
+};
+
+// fragments that are likely to push the reader and parser down unusual paths
+const FRAGMENTS: &[&str] = &[
+    "<",
+    ">",
+    "/>",
+    "</",
+    "<ABR>",
+    "</ABR>",
+    "<ABN>",
+    "</ABN>",
+    "<ABN status=\"ACT\" ABNStatusFromDate=\"20000101\">",
+    "<GST status=\"NON\" GSTStatusFromDate=\"19000101\" />",
+    "<OtherEntity><NonIndividualName type=\"TRD\"><NonIndividualNameText>X</NonIndividualNameText></NonIndividualName></OtherEntity>",
+    "<LegalEntity><IndividualName type=\"LGL\"><FamilyName>X</FamilyName></IndividualName></LegalEntity>",
+    "<GivenName>",
+    "</GivenName>",
+    "<a><b><c><d><e><f><g><h><i>",
+    "=",
+    "\"",
+    "'",
+    "&",
+    "&amp;",
+    "&#x0;",
+    "&#1114112;",
+    "&#x1F600;",
+    "&unknown;",
+    "<!--",
+    "-->",
+    "<![CDATA[",
+    "]]>",
+    "<?",
+    "?>",
+    "<!DOCTYPE",
+    " ",
+    "\n",
+    "20241127",
+    "99999999",
+    "0000",
+];
+
+#[derive(Debug, Clone)]
+enum Mutation {
+    Delete { at: usize, len: usize },
+    Insert { at: usize, bytes: Vec<u8> },
+    Fragment { at: usize, index: usize },
+    Duplicate { at: usize, len: usize },
+    Replace { at: usize, byte: u8 },
+}
+
+impl Mutation {
+    // positions are taken modulo the current length so every mutation applies to any input
+    fn apply(&self, input: &mut Vec<u8>) {
+        let at = |x: usize| x % (input.len() + 1);
+        match *self {
+            Mutation::Delete { at: i, len } => {
+                let i = at(i);
+                let end = (i + len).min(input.len());
+                input.drain(i..end);
+            }
+            Mutation::Insert { at: i, ref bytes } => {
+                let i = at(i);
+                input.splice(i..i, bytes.iter().copied());
+            }
+            Mutation::Fragment { at: i, index } => {
+                let i = at(i);
+                let fragment = FRAGMENTS[index % FRAGMENTS.len()].bytes();
+                input.splice(i..i, fragment);
+            }
+            Mutation::Duplicate { at: i, len } => {
+                let i = at(i);
+                let end = (i + len).min(input.len());
+                let copy = input[i..end].to_vec();
+                input.splice(end..end, copy);
+            }
+            Mutation::Replace { at: i, byte } => {
+                if !input.is_empty() {
+                    let i = i % input.len();
+                    input[i] = byte;
+                }
+            }
+        }
+    }
+}
+
+fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        (any::<usize>(), 1..64usize).prop_map(|(at, len)| Mutation::Delete { at, len }),
+        (any::<usize>(), prop::collection::vec(any::<u8>(), 1..16))
+            .prop_map(|(at, bytes)| Mutation::Insert { at, bytes }),
+        (any::<usize>(), any::<usize>()).prop_map(|(at, index)| Mutation::Fragment { at, index }),
+        (any::<usize>(), 1..256usize).prop_map(|(at, len)| Mutation::Duplicate { at, len }),
+        (any::<usize>(), any::<u8>()).prop_map(|(at, byte)| Mutation::Replace { at, byte }),
+    ]
+}
+
+/// Valid records to mutate: the golden files plus a spread of generated records.
+fn seeds() -> &'static [String] {
+    static SEEDS: OnceLock<Vec<String>> = OnceLock::new();
+    SEEDS.get_or_init(|| {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+        let mut seeds: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|x| x.unwrap().path())
+            .filter(|x| x.extension().is_some_and(|x| x == "xml"))
+            .map(|x| fs::read_to_string(x).unwrap())
+            .collect();
+        seeds.sort();
+        let options = GenerateOptions {
+            replaced: 0.1,
+            malformed: 0.2,
+            ..Default::default()
+        };
+        seeds.extend(Generator::new(options).take(200));
+        seeds
+    })
+}
+
+fn check(xml: &str) -> Result<(), TestCaseError> {
+    if let Ok(record) = parse_record(xml) {
+        let json = serde_json::to_string(&record).unwrap();
+        let back: ABNRecord = serde_json::from_str(&json)
+            .map_err(|e| TestCaseError::fail(format!("{e} in {json}")))?;
+        prop_assert_eq!(&back, &record);
+
+        let xml = emit_record(&record);
+        let back =
+            parse_record(&xml).map_err(|e| TestCaseError::fail(format!("{e:#} in {xml}")))?;
+        prop_assert_eq!(&back, &record);
+        prop_assert_eq!(emit_record(&back), xml);
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        timeout: 10_000,
+        failure_persistence: Some(Box::new(FileFailurePersistence::WithSource("proptest-regressions"))),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn arbitrary_text(xml in any::<String>()) {
+        check(&xml)?;
+    }
+
+    #[test]
+    fn arbitrary_markup(xml in "(<[A-Za-z/!?]{0,3}[^<>]{0,20}>?|[^<]{0,10}|&[#x0-9a-z]{0,6};?){0,30}") {
+        check(&xml)?;
+    }
+
+    #[test]
+    fn mutated_records(
+        seed in any::<prop::sample::Index>(),
+        mutations in prop::collection::vec(mutation(), 1..8),
+    ) {
+        let seeds = seeds();
+        let mut input = seed.get(seeds).clone().into_bytes();
+        for x in &mutations {
+            x.apply(&mut input);
+        }
+        check(&String::from_utf8_lossy(&input))?;
+    }
+
+    #[test]
+    fn generated_records_round_trip(seed in any::<u64>()) {
+        let options = GenerateOptions {
+            records: 1,
+            seed,
+            ..Default::default()
+        };
+        let xml = Generator::new(options).next().unwrap();
+        prop_assert!(parse_record(&xml).is_ok(), "generated record rejected: {}", xml);
+        check(&xml)?;
+    }
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::{extract, records};
+use simple_abns::{
+    generate::{generate, GenerateOptions, Generator, Weights},
+    model::{EntityName, EntityType},
+    parser::parse_record,
+};
+
+fn valid_abn(abn: &str) -> bool {
+    const WEIGHTS: [u32; 11] = [10, 1, 3, 5, 7, 9, 11, 13, 15, 17, 19];
+    let digits: Vec<_> = abn.bytes().map(|x| u32::from(x - b'0')).collect();
+    let sum: u32 = digits
+        .iter()
+        .zip(WEIGHTS)
+        .enumerate()
+        .map(|(i, (d, w))| (d - u32::from(i == 0)) * w)
+        .sum();
+    abn.len() == 11 && abn.bytes().all(|x| x.is_ascii_digit()) && sum.is_multiple_of(89)
+}
+
+fn valid_acn(acn: &str) -> bool {
+    let digits: Vec<_> = acn.bytes().map(|x| u32::from(x - b'0')).collect();
+    let sum: u32 = digits[..8]
+        .iter()
+        .zip((1..=8).rev())
+        .map(|(d, w)| d * w)
+        .sum();
+    acn.len() == 9 && acn.bytes().all(|x| x.is_ascii_digit()) && (10 - sum % 10) % 10 == digits[8]
+}
+
+#[test]
+fn checksums() {
+    // known numbers, and the same with a digit changed
+    assert!(valid_abn("53004085616") && !valid_abn("53004085617"));
+    assert!(valid_acn("004085616") && !valid_acn("004085617"));
+
+    let records = records(&extract(2000, 0));
+    assert_eq!(records.len(), 2000);
+    for record in &records {
+        assert!(valid_abn(&record.abn), "{}", record.abn);
+        if let Some(acn) = &record.asic_number {
+            assert!(valid_acn(acn), "{acn}");
+            // companies' abns are their acn with a prefix
+            assert_eq!(record.abn[2..], *acn);
+        }
+    }
+    let companies = records.iter().filter(|x| x.asic_number.is_some()).count();
+    assert!(companies > 100, "{companies}");
+}
+
+#[test]
+fn seeds() {
+    assert_eq!(extract(500, 7), extract(500, 7));
+    assert_ne!(extract(500, 7), extract(500, 8));
+
+    // more records only add to the end
+    let mut longer = Generator::new(GenerateOptions {
+        seed: 7,
+        ..Default::default()
+    });
+    let shorter = String::from_utf8(extract(500, 7)).unwrap();
+    let records: Vec<_> = shorter.lines().filter(|x| x.starts_with("<ABR")).collect();
+    assert_eq!(records.len(), 500);
+    for record in records {
+        assert_eq!(longer.next().unwrap(), record);
+    }
+}
+
+#[test]
+fn entity_types() {
+    let weights: Weights = " IND = 1, PRV=0".parse().unwrap();
+    let options = GenerateOptions {
+        records: 200,
+        entity_types: Some(weights),
+        ..Default::default()
+    };
+    let mut extract = Vec::new();
+    generate(&options, &mut extract).unwrap();
+    for record in records(&extract) {
+        assert_eq!(record.entity_type, EntityType::IND);
+        assert!(matches!(record.entity_name, EntityName::Individual { .. }));
+    }
+
+    assert!("IND=0,PRV=0".parse::<Weights>().is_err());
+    assert!("XYZ=1".parse::<Weights>().is_err());
+    assert!("IND=-1".parse::<Weights>().is_err());
+}
+
+#[test]
+fn malformed() {
+    let rejected = |malformed: f64| {
+        let generator = Generator::new(GenerateOptions {
+            malformed,
+            ..Default::default()
+        });
+        generator
+            .take(2000)
+            .filter(|x| parse_record(x).is_err())
+            .count()
+    };
+
+    // every way of corrupting a record is one the parser rejects, and nothing else is
+    assert_eq!(rejected(0.0), 0);
+    assert_eq!(rejected(1.0), 2000);
+    let some = rejected(0.2);
+    assert!((300..500).contains(&some), "{some}");
+}
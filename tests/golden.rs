@@ -0,0 +1,117 @@
+//! Every `tests/golden/*.xml` record is parsed and compared against the `.json` file next to
+//! it, or the `.err` file for records that should be rejected. Run with `UPDATE_GOLDEN=1` to
+//! rewrite the expected files after an intentional change, and review the diff.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use simple_abns::{convert::legacy_names, emit::emit_record, parser::parse_record};
+
+fn golden_records() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|x| x.extension().is_some_and(|x| x == "xml"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no golden records in {dir:?}");
+    paths
+}
+
+#[test]
+fn golden() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let paths = golden_records();
+
+    let mut failures = Vec::new();
+    for path in &paths {
+        let xml = fs::read_to_string(path).unwrap();
+        let (actual, expected_path, other_path) = match parse_record(&xml) {
+            Ok(record) => (
+                serde_json::to_string_pretty(&record).unwrap() + "\n",
+                path.with_extension("json"),
+                path.with_extension("err"),
+            ),
+            Err(e) => (
+                format!("{e:#}\n"),
+                path.with_extension("err"),
+                path.with_extension("json"),
+            ),
+        };
+
+        if update {
+            fs::write(&expected_path, &actual).unwrap();
+            let _ = fs::remove_file(&other_path);
+            continue;
+        }
+
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => (),
+            Ok(expected) => failures.push(format!(
+                "{}:\n--- expected\n{expected}--- actual\n{actual}",
+                expected_path.display()
+            )),
+            Err(_) => failures.push(format!(
+                "{} is missing, got:\n{actual}",
+                expected_path.display()
+            )),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} golden records changed:\n\n{}",
+        failures.len(),
+        paths.len(),
+        failures.join("\n")
+    );
+}
+
+/// Every golden record that parses comes back the same after writing it out as XML again.
+#[test]
+fn emit_round_trip() {
+    for path in golden_records() {
+        let Ok(record) = parse_record(&fs::read_to_string(&path).unwrap()) else {
+            continue;
+        };
+        let xml = emit_record(&record);
+        match parse_record(&xml) {
+            Ok(back) => assert_eq!(back, record, "{} changed, emitted as {xml}", path.display()),
+            Err(e) => panic!("{} emitted as {xml}, which failed: {e:#}", path.display()),
+        }
+    }
+}
+
+/// The compatibility output still matches what was written before other name types were kept.
+#[test]
+fn legacy_names_output() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let record = parse_record(&fs::read_to_string(dir.join("mixed_other_names.xml")).unwrap());
+    let actual = serde_json::to_string_pretty(&legacy_names(&record.unwrap()).unwrap()).unwrap();
+    let expected = r#"{
+  "abn": "88712649015",
+  "status": "Active",
+  "status_since": "1999-11-01",
+  "last_updated": "2024-04-12",
+  "entity_name": {
+    "type": "NonIndividual",
+    "name": "EXAMPLE HOLDINGS PTY LTD"
+  },
+  "entity_type": "PRV",
+  "business_names": [
+    "EXAMPLE BUILDING"
+  ],
+  "trade_names": [
+    "EXAMPLE HOMES",
+    "EXAMPLE BROTHERS"
+  ],
+  "postcode": "3000",
+  "state": "VIC",
+  "gst_status": "Active",
+  "gst_status_since": "2000-07-01"
+}"#;
+    assert_eq!(actual, expected);
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{extract, indexed, records, TempDir};
+use simple_abns::{
+    filter::Filter,
+    index::{index_path, Dataset, Index},
+    model::{ABNRecord, EntityName, Status},
+};
+
+/// Generated records renamed to `names` in turn, without other names to search on.
+fn named(names: &[&str]) -> Vec<ABNRecord> {
+    let mut records = records(&extract(names.len(), 0));
+    for (record, name) in records.iter_mut().zip(names) {
+        record.entity_name = EntityName::NonIndividual {
+            name: name.to_string(),
+        };
+        record.trust = None;
+        record.other_names.clear();
+        record.status = Status::Active;
+    }
+    records
+}
+
+fn search(index: &Index, data: &Path, query: &str, limit: usize) -> Vec<String> {
+    let mut dataset = Dataset::open(data).unwrap();
+    index
+        .search(&mut dataset, query, &Filter::default(), limit)
+        .unwrap()
+        .into_iter()
+        .map(|x| x.record.abn)
+        .collect()
+}
+
+#[test]
+fn lookups() {
+    let dir = TempDir::new("index-lookups");
+    let records = records(&extract(500, 0));
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+    let mut dataset = Dataset::open(&data).unwrap();
+    assert_eq!(index.record_count(), records.len());
+
+    let record = &records[123];
+    let abn = &record.abn;
+    let spaced = format!("{} {} {} {}", &abn[..2], &abn[2..5], &abn[5..8], &abn[8..]);
+    for abn in [abn.as_str(), &spaced] {
+        let id = index.abn(abn).unwrap();
+        assert_eq!(&dataset.get(index.offset(id)).unwrap(), record, "{abn}");
+    }
+    assert_eq!(index.abn("00000000000"), None);
+    assert_eq!(index.abn("not an abn"), None);
+
+    let record = records.iter().find(|x| x.asic_number.is_some()).unwrap();
+    let acn = record.asic_number.as_ref().unwrap();
+    let ids = index.acn(&format!("{} {} {}", &acn[..3], &acn[3..6], &acn[6..]));
+    assert!(ids
+        .iter()
+        .any(|&id| dataset.get(index.offset(id)).unwrap() == *record));
+}
+
+#[test]
+fn save_and_load() {
+    let dir = TempDir::new("index-files");
+    let records = named(&["SMITH PLUMBING", "JONES PLUMBING", "SMITH BAKERY"]);
+    let data = indexed(&dir, &records);
+    let path = index_path(&data);
+
+    let built = Index::build(&data).unwrap();
+    let loaded = Index::load(&path).unwrap();
+    assert_eq!(loaded.record_count(), built.record_count());
+    assert_eq!(loaded.exact("SMITH"), built.exact("SMITH"));
+    assert_eq!(loaded.exact("SMITH"), [0, 2]);
+    assert_eq!(search(&loaded, &data, "plumbing", 10).len(), 2);
+
+    let bytes = fs::read(&path).unwrap();
+    let error = |bytes: &[u8]| {
+        fs::write(&path, bytes).unwrap();
+        Index::load(&path).err().unwrap().to_string()
+    };
+    assert_eq!(error(&bytes[..bytes.len() - 1]), "truncated search index");
+    let mut old = bytes.clone();
+    old[..8].copy_from_slice(b"SABNIDX2");
+    assert!(error(&old).contains("rebuild it"));
+    assert_eq!(error(b"{\"abn\":\"1\"}\n"), "not a search index");
+}
+
+#[test]
+fn ranking() {
+    let dir = TempDir::new("index-ranking");
+    let records = named(&[
+        "SMITHSON PLUMBING",
+        "SMITH PLUMBING AND GAS SERVICES",
+        "SMITH PLUMBING",
+        "SMITH ELECTRICAL",
+    ]);
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+
+    // exact words beat prefixes, and fewer other words beat more
+    let abns = |ids: &[usize]| -> Vec<_> { ids.iter().map(|&i| records[i].abn.clone()).collect() };
+    let found = search(&index, &data, "smith plumbing", 10);
+    assert_eq!(found.len(), 3);
+    assert_eq!(found[0], records[2].abn);
+    assert_eq!(search(&index, &data, "smith plumbing", 1), abns(&[2]));
+    assert_eq!(search(&index, &data, "smi elec", 10), abns(&[3]));
+    assert!(search(&index, &data, "smith bakery", 10).is_empty());
+
+    let mut dataset = Dataset::open(&data).unwrap();
+    let filter = Filter {
+        state: vec![records[1].state.clone().unwrap_or_default()],
+        ..Default::default()
+    };
+    let hits = index.search(&mut dataset, "plumbing", &filter, 10).unwrap();
+    assert!(hits.iter().all(|x| x.record.state == records[1].state));
+}
+
+#[test]
+fn short_prefixes() {
+    let dir = TempDir::new("index-short");
+    let records = named(&["SMITH PLUMBING", "SM TRADING"]);
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+
+    // two letters are only matched as a whole word
+    assert_eq!(search(&index, &data, "sm", 10), [records[1].abn.clone()]);
+    assert_eq!(search(&index, &data, "smi", 10), [records[0].abn.clone()]);
+}
+
+#[test]
+fn broad_queries() {
+    let dir = TempDir::new("index-broad");
+    let mut names: Vec<_> = (0..2500).map(|i| format!("ACMEX {i}")).collect();
+    names.extend((0..5).map(|i| format!("ACME {i}")));
+    let names: Vec<_> = names.iter().map(|x| x.as_str()).collect();
+    let records = named(&names);
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+
+    // only some of the records are read, ranked by their words before any are
+    assert_eq!(search(&index, &data, "acme", 5000).len(), 2000);
+    let exact: Vec<_> = records[2500..].iter().map(|x| x.abn.clone()).collect();
+    let mut found = search(&index, &data, "acme", 5);
+    found.sort();
+    let mut expected = exact.clone();
+    expected.sort();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn broad_filtered_queries() {
+    let dir = TempDir::new("index-broad-filtered");
+    let names: Vec<_> = (0..2500).map(|i| format!("ACME {i}")).collect();
+    let names: Vec<_> = names.iter().map(|x| x.as_str()).collect();
+    let mut records = named(&names);
+    for (i, record) in records.iter_mut().enumerate() {
+        record.state = Some(if i < 2490 { "VIC" } else { "TAS" }.into());
+    }
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+
+    // ties are ranked by id, so the records that pass the filter are past the first 2000
+    let mut dataset = Dataset::open(&data).unwrap();
+    let filter = Filter {
+        state: vec!["TAS".into()],
+        ..Default::default()
+    };
+    let mut found: Vec<_> = index
+        .search(&mut dataset, "acme", &filter, 20)
+        .unwrap()
+        .into_iter()
+        .map(|x| x.record.abn)
+        .collect();
+    found.sort();
+    let mut expected: Vec<_> = records[2490..].iter().map(|x| x.abn.clone()).collect();
+    expected.sort();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn stale_index() {
+    let dir = TempDir::new("index-stale");
+    let records = named(&["SMITH PLUMBING", "JONES PLUMBING"]);
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+
+    // the dataset is replaced by a shorter one, so the offsets are out of date
+    let lines: String = records
+        .iter()
+        .map(|x| serde_json::to_string(x).unwrap()[1..].to_string() + "\n")
+        .collect();
+    fs::write(&data, lines).unwrap();
+    let mut dataset = Dataset::open(&data).unwrap();
+    let error = index
+        .search(&mut dataset, "plumbing", &Filter::default(), 10)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("is the index stale?"), "{error}");
+}
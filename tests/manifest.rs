@@ -0,0 +1,71 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use chrono::NaiveDate;
+use common::{extract, TempDir};
+use simple_abns::{
+    convert::{convert, ConvertArgs},
+    manifest::{extract_date, sha256, Manifest},
+    model::SCHEMA_VERSION,
+    output::{CompressionArgs, Writer},
+};
+
+#[test]
+fn manifest() {
+    let dir = TempDir::new("manifest");
+    let input = dir.join("20241127_Public01.xml");
+    let extract = extract(500, 0);
+    fs::write(&input, &extract).unwrap();
+
+    let path = dir.join("out.jsonl.zst");
+    let mut output = Writer::create(&path, CompressionArgs::default(), 1, None).unwrap();
+    convert(&extract[..], &mut output, &ConvertArgs::default()).unwrap();
+    let written = output.finish().unwrap();
+
+    let manifest = Manifest::new(
+        &[&input],
+        &[(path.clone(), written)],
+        vec!["simple-abns".into(), "convert".into()],
+    )
+    .unwrap();
+    assert_eq!(manifest.simple_abns_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+    assert_eq!(manifest.extract_date, NaiveDate::from_ymd_opt(2024, 11, 27));
+    assert_eq!(manifest.inputs[0].bytes, extract.len() as u64);
+    assert_eq!(manifest.inputs[0].sha256, sha256(&input).unwrap().1);
+    assert_eq!(manifest.outputs[0].path, path);
+    assert_eq!(
+        manifest.outputs[0].bytes,
+        fs::metadata(&path).unwrap().len()
+    );
+    assert_eq!(manifest.outputs[0].records, 500);
+
+    let json = serde_json::to_string(&manifest).unwrap();
+    assert_eq!(serde_json::from_str::<Manifest>(&json).unwrap(), manifest);
+}
+
+#[test]
+fn hashes() {
+    let dir = TempDir::new("hash");
+    let path = dir.join("abc");
+    fs::write(&path, "abc").unwrap();
+    assert_eq!(
+        sha256(&path).unwrap(),
+        (
+            3,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into()
+        )
+    );
+}
+
+#[test]
+fn extract_dates() {
+    let date = |x| extract_date(Path::new(x));
+    assert_eq!(
+        date("raw/20241127_Public01.xml"),
+        NaiveDate::from_ymd_opt(2024, 11, 27)
+    );
+    assert_eq!(date("raw/Public01.xml"), None);
+    assert_eq!(date("raw/2024"), None);
+}
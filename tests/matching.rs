@@ -0,0 +1,135 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::{extract, indexed, records, TempDir};
+use simple_abns::{
+    index::{index_path, Dataset, Index},
+    matching::{find, similarity, trigrams, Row},
+    model::{ABNRecord, EntityName, Status},
+};
+
+/// Generated records renamed to `names` in turn, in one state and postcode.
+fn named(names: &[&str]) -> Vec<ABNRecord> {
+    let mut records = records(&extract(names.len(), 0));
+    for (record, name) in records.iter_mut().zip(names) {
+        record.entity_name = EntityName::NonIndividual {
+            name: name.to_string(),
+        };
+        record.trust = None;
+        record.other_names.clear();
+        record.status = Status::Active;
+        record.state = Some("NSW".into());
+        record.postcode = Some("2000".into());
+    }
+    records
+}
+
+fn row(name: &str) -> Row {
+    Row {
+        name: name.into(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn dice_scores() {
+    let score = |a, b| similarity(&trigrams(a), &trigrams(b));
+    assert_eq!(score("SMITH", "SMITH"), 1.0);
+    assert_eq!(score("ABC", "XYZ"), 0.0);
+    assert_eq!(similarity(&HashSet::new(), &HashSet::new()), 0.0);
+    // "  A", " AB", "AB " and "  A", " AC", "AC " share one of six
+    assert!((score("AB", "AC") - 1.0 / 3.0).abs() < 1e-9);
+    assert_eq!(score("AB", "AC"), score("AC", "AB"));
+    assert!(score("WOOLWORTHS", "WOOLWORHS") > score("WOOLWORTHS", "WOOLIES"));
+}
+
+#[test]
+fn candidates() {
+    let dir = TempDir::new("matching");
+    let records = named(&[
+        "WOOLWORTHS GROUP LIMITED",
+        "WOOLWORTHS SUPERMARKETS",
+        "SMITH PLUMBING PTY LTD",
+        "SMITH ELECTRICAL PTY LTD",
+        "SMITHS CHIPS",
+    ]);
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+    let mut dataset = Dataset::open(&data).unwrap();
+
+    let found = find(&index, &mut dataset, &row("Smith Plumbing"), 3).unwrap();
+    assert_eq!(found[0].record.abn, records[2].abn);
+    assert_eq!(found[0].score, 1.0);
+    assert!(found.windows(2).all(|x| x[0].score >= x[1].score));
+
+    // only as many as were asked for
+    let found = find(&index, &mut dataset, &row("smith"), 1).unwrap();
+    assert_eq!(found.len(), 1);
+
+    // a typo in every word still finds candidates
+    let found = find(&index, &mut dataset, &row("WOOLWORHS GROUPP"), 2).unwrap();
+    assert_eq!(found[0].record.abn, records[0].abn);
+    assert!(find(&index, &mut dataset, &row("QX"), 2)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn location_breaks_ties() {
+    let dir = TempDir::new("matching-location");
+    let mut records = named(&["BRIGHT CLEANING", "BRIGHT CLEANING", "BRIGHT CLEANING"]);
+    records[1].state = Some("VIC".into());
+    records[1].postcode = Some("3000".into());
+    records[2].state = Some("VIC".into());
+    records[2].postcode = Some("3121".into());
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+    let mut dataset = Dataset::open(&data).unwrap();
+
+    let mut first = |postcode: Option<&str>, state: Option<&str>| {
+        let row = Row {
+            name: "Bright Cleaning".into(),
+            postcode: postcode.map(Into::into),
+            state: state.map(Into::into),
+        };
+        let found = find(&index, &mut dataset, &row, 3).unwrap();
+        assert_eq!(found.len(), 3);
+        (found[0].record.abn.clone(), found[0].location)
+    };
+    assert_eq!(
+        first(Some("3121"), Some("VIC")),
+        (records[2].abn.clone(), 2)
+    );
+    assert_eq!(first(Some(" 3000 "), None), (records[1].abn.clone(), 2));
+    assert_eq!(first(None, Some(" vic")), (records[1].abn.clone(), 1));
+    assert_eq!(first(Some(""), Some("")).1, 0);
+}
+
+#[test]
+fn tied_candidates() {
+    let dir = TempDir::new("matching-tied");
+    let records = named(&["ACME TRADING"; 2500]);
+    let data = indexed(&dir, &records);
+    let index = Index::load(&index_path(&data)).unwrap();
+    let mut dataset = Dataset::open(&data).unwrap();
+
+    // more candidates than are read, all with the same score, so which are read decides the
+    // output and has to be the same every time
+    let mut find = || -> Vec<_> {
+        find(&index, &mut dataset, &row("Acme Trading"), 10)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.record.abn)
+            .collect()
+    };
+    let first = find();
+    assert_eq!(first.len(), 10);
+    for _ in 0..5 {
+        assert_eq!(find(), first);
+    }
+    let mut read: Vec<_> = records[..2000].iter().map(|x| &x.abn).collect();
+    read.sort();
+    let expected: Vec<_> = read.iter().take(10).map(|x| x.to_string()).collect();
+    assert_eq!(first, expected);
+}
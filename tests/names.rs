@@ -0,0 +1,50 @@
+use simple_abns::model::{title_case, EntityName};
+
+fn individual(title: Option<&str>, given: &[&str], family: &str) -> EntityName {
+    EntityName::Individual {
+        title: title.map(Into::into),
+        given: given.first().map(|x| x.to_string()),
+        given_2: given.get(1).map(|x| x.to_string()),
+        family: family.into(),
+    }
+}
+
+#[test]
+fn title_cases() {
+    let cases = [
+        ("SMITH", "Smith"),
+        ("MCDONALD", "McDonald"),
+        ("MACLEOD", "MacLeod"),
+        ("MACHIN", "Machin"),
+        ("MACK", "Mack"),
+        ("MACE", "Mace"),
+        ("O'BRIEN", "O'Brien"),
+        ("D'ARCY", "D'Arcy"),
+        ("SMITH-JONES", "Smith-Jones"),
+        ("MARY-JANE", "Mary-Jane"),
+        ("VAN DER BERG", "Van Der Berg"),
+        ("ZOË", "Zoë"),
+        ("mc", "Mc"),
+    ];
+    for (name, expected) in cases {
+        assert_eq!(title_case(name), expected, "{name}");
+    }
+}
+
+#[test]
+fn display_names() {
+    let name = individual(Some("DR"), &["MARY-JANE", "ANNE"], "MCDONALD");
+    assert_eq!(name.display_name(), "Dr Mary-Jane Anne McDonald");
+    assert_eq!(name.sort_name(), "MCDONALD, Mary-Jane Anne");
+    assert_eq!(name.full_name(), "MARY-JANE ANNE MCDONALD");
+
+    let name = individual(None, &[], "O'NEIL");
+    assert_eq!(name.display_name(), "O'Neil");
+    assert_eq!(name.sort_name(), "O'NEIL");
+
+    let name = EntityName::NonIndividual {
+        name: "MCDONALD & SONS PTY LTD".into(),
+    };
+    assert_eq!(name.display_name(), "MCDONALD & SONS PTY LTD");
+    assert_eq!(name.sort_name(), "MCDONALD & SONS PTY LTD");
+}
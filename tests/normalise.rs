@@ -0,0 +1,59 @@
+use simple_abns::{
+    model::EntityName,
+    normalise::{core_key, entity_match_key, match_key, tokens},
+};
+
+#[test]
+fn tokens_drop_punctuation() {
+    assert_eq!(
+        tokens("O'Brien's  Bakery-Cafe"),
+        ["OBRIENS", "BAKERY", "CAFE"]
+    );
+    assert_eq!(tokens("A&B Pty. Ltd."), ["A", "AND", "B", "PTY", "LTD"]);
+}
+
+#[test]
+fn match_keys() {
+    let cases = [
+        ("The Smith & Sons Pty. Ltd.", "SMITH AND SONS PTY LTD"),
+        (
+            "SMITH AND SONS PROPRIETARY LIMITED",
+            "SMITH AND SONS PTY LTD",
+        ),
+        ("  smith   and sons pty ltd ", "SMITH AND SONS PTY LTD"),
+        (
+            "THE TRUSTEE FOR THE JONES FAMILY TRUST",
+            "JONES FAMILY TRUST",
+        ),
+        ("Trustee for Jones Family Trust", "JONES FAMILY TRUST"),
+        ("KOALA ASSOCIATION INCORPORATED", "KOALA ASSOCIATION INC"),
+    ];
+    for (name, key) in cases {
+        assert_eq!(match_key(name), key, "{name}");
+    }
+}
+
+#[test]
+fn core_keys() {
+    assert_eq!(
+        core_key("Smith & Sons Proprietary Limited"),
+        "SMITH AND SONS"
+    );
+    assert_eq!(
+        core_key("THE TRUSTEE FOR JONES DISCRETIONARY TRUST"),
+        "JONES"
+    );
+    // a name that's nothing but a legal form keeps its last word
+    assert_eq!(core_key("LIMITED"), "LTD");
+}
+
+#[test]
+fn individual_keys() {
+    let name = EntityName::Individual {
+        title: Some("MR".into()),
+        given: Some("John".into()),
+        given_2: None,
+        family: "O'Neil".into(),
+    };
+    assert_eq!(entity_match_key(&name), "JOHN ONEIL");
+}
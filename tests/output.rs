@@ -0,0 +1,282 @@
+mod common;
+
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+use common::{records, TempDir};
+use flate2::read::GzDecoder;
+use simple_abns::{
+    convert::{convert, convert_inputs, ConvertArgs, Input},
+    model::ABNRecord,
+    output::{CompressionArgs, Format, ShardKey, Shards, Writer},
+    progress::Progress,
+};
+
+fn extract() -> Vec<u8> {
+    common::extract(2000, 0)
+}
+
+fn read_shards(dir: &Path) -> Vec<(String, Vec<ABNRecord>)> {
+    let mut shards: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|x| {
+            let path = x.unwrap().path();
+            let file = File::open(&path).unwrap();
+            let name = path.file_name().unwrap().to_str().unwrap();
+            let (shard, input): (_, Box<dyn Read>) =
+                if let Some(shard) = name.strip_suffix(".jsonl.zst") {
+                    (shard, Box::new(zstd::Decoder::new(file).unwrap()))
+                } else if let Some(shard) = name.strip_suffix(".jsonl.gz") {
+                    (shard, Box::new(GzDecoder::new(file)))
+                } else {
+                    panic!("unexpected file {path:?}");
+                };
+            let records = BufReader::new(input)
+                .lines()
+                .map(|x| serde_json::from_str(&x.unwrap()).unwrap())
+                .collect();
+            (shard.to_string(), records)
+        })
+        .collect();
+    shards.sort_by(|a, b| a.0.cmp(&b.0));
+    shards
+}
+
+#[test]
+fn shards() {
+    let extract = extract();
+    let mut all = Vec::new();
+    convert(&extract[..], &mut all, &ConvertArgs::default()).unwrap();
+    let total = all.iter().filter(|x| **x == b'\n').count();
+
+    for (key, extension) in [
+        (ShardKey::State, "jsonl.zst"),
+        (ShardKey::EntityType, "jsonl.gz"),
+        (ShardKey::Status, "jsonl.zst"),
+        (ShardKey::Abn, "jsonl.gz"),
+    ] {
+        let dir = TempDir::new("shards");
+        let template = dir.join(format!("{{}}.{extension}"));
+        let mut output =
+            Shards::new(template.to_str().unwrap(), key, Default::default(), None).unwrap();
+        convert(&extract[..], &mut output, &ConvertArgs::default()).unwrap();
+        output.finish().unwrap();
+
+        let shards = read_shards(dir.path());
+        assert!(
+            shards.len() > 1,
+            "{key:?} only made {} shards",
+            shards.len()
+        );
+        let mut count = 0;
+        for (shard, records) in &shards {
+            for record in records {
+                assert_eq!(&key.shard(record), shard, "{key:?}");
+            }
+            count += records.len();
+        }
+        assert_eq!(count, total, "{key:?}");
+    }
+}
+
+#[test]
+fn shard_template() {
+    let shards = |template| Shards::new(template, ShardKey::State, Default::default(), None);
+    assert!(shards("out.jsonl").is_err());
+    assert!(shards("{}/{}.jsonl").is_err());
+    assert!(shards("out/{}.jsonl").is_ok());
+}
+
+#[test]
+fn compression() {
+    let extract = extract();
+    let mut all = Vec::new();
+    convert(&extract[..], &mut all, &ConvertArgs::default()).unwrap();
+
+    let dir = TempDir::new("compression");
+    for (name, level, threads) in [
+        ("fast.jsonl.zst", Some(1), 1),
+        ("threaded.jsonl.zst", Some(19), 4),
+        ("default.jsonl.gz", None, 1),
+        ("best.jsonl.gz", Some(9), 1),
+    ] {
+        let path = dir.join(name);
+        let compression = CompressionArgs {
+            compression_level: level,
+            zstd_threads: Some(threads),
+        };
+        let mut output = Writer::create(&path, compression, threads, None).unwrap();
+        convert(&extract[..], &mut output, &ConvertArgs::default()).unwrap();
+        output.finish().unwrap();
+
+        let file = File::open(&path).unwrap();
+        let mut input: Box<dyn Read> = match path.extension().unwrap().to_str() {
+            Some("zst") => Box::new(zstd::Decoder::new(file).unwrap()),
+            _ => Box::new(GzDecoder::new(file)),
+        };
+        let mut output = Vec::new();
+        input.read_to_end(&mut output).unwrap();
+        assert!(output == all, "{name} didn't round trip");
+    }
+
+    for (name, level) in [
+        ("out.jsonl.zst", 0),
+        ("out.jsonl.zst", 23),
+        ("out.jsonl.gz", 10),
+        ("out.jsonl", 1),
+    ] {
+        let compression = CompressionArgs {
+            compression_level: Some(level),
+            zstd_threads: None,
+        };
+        assert!(
+            Writer::create(&dir.join(name), compression, 1, None).is_err(),
+            "{name} {level}"
+        );
+    }
+}
+
+#[test]
+fn csv() {
+    let extract = extract();
+    let records = records(&extract);
+
+    let args = ConvertArgs {
+        format: Format::Csv,
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    convert(&extract[..], &mut output, &args).unwrap();
+    let mut rows = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(&output[..])
+        .into_records()
+        .map(|x| x.unwrap());
+    let row = rows.next().unwrap();
+    assert_eq!(&row[0], records[0].abn);
+    assert_eq!(rows.count(), records.len() - 1);
+
+    let args = ConvertArgs {
+        format: Format::Csv,
+        fields: Some("abn,other_names.name:names,state".parse().unwrap()),
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    convert(&extract[..], &mut output, &args).unwrap();
+    let rows: Vec<_> = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(&output[..])
+        .into_records()
+        .map(|x| x.unwrap())
+        .collect();
+    let record = records
+        .iter()
+        .position(|x| x.other_names.len() > 1)
+        .unwrap();
+    let names: Vec<_> = records[record]
+        .other_names
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect();
+    assert_eq!(&rows[record][1], names.join("; "));
+    assert_eq!(rows[record].len(), 3);
+}
+
+#[test]
+fn format_from_path() {
+    for (path, format) in [
+        ("out.csv", Format::Csv),
+        ("out.csv.zst", Format::Csv),
+        ("out/{}.csv.gz", Format::Csv),
+        ("out.jsonl.zst", Format::Json),
+        ("out.zst", Format::Json),
+        ("out", Format::Json),
+        ("out.arrow", Format::Arrow),
+        ("out.feather", Format::Arrow),
+        ("out.arrows", Format::Arrow),
+        ("out.sql", Format::Postgres),
+        ("out/abns.sql", Format::Postgres),
+    ] {
+        assert_eq!(
+            Format::from_path(Path::new(path)).unwrap(),
+            format,
+            "{path}"
+        );
+    }
+    for path in [
+        "out.arrow.zst",
+        "out.arrow.gz",
+        "out.arrows.zst",
+        "out.sql.zst",
+        "out.sql.gz",
+    ] {
+        assert!(Format::from_path(Path::new(path)).is_err(), "{path}");
+    }
+}
+
+#[test]
+fn deterministic_order() {
+    let extract = extract();
+    let mut expected = Vec::new();
+    convert(&extract[..], &mut expected, &ConvertArgs::default()).unwrap();
+
+    for (threads, chunk_size) in [(1, 1), (2, 7), (8, 100), (3, 100_000)] {
+        let args = ConvertArgs {
+            threads: Some(threads),
+            chunk_size: Some(chunk_size),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        convert(&extract[..], &mut output, &args).unwrap();
+        assert!(
+            output == expected,
+            "{threads} threads, {chunk_size} line chunks"
+        );
+    }
+
+    let args = ConvertArgs {
+        chunk_size: Some(0),
+        ..Default::default()
+    };
+    assert!(convert(&extract[..], &mut Vec::new(), &args).is_err());
+}
+
+#[test]
+fn several_inputs() {
+    let extract = extract();
+    let mut expected = Vec::new();
+    convert(&extract[..], &mut expected, &ConvertArgs::default()).unwrap();
+    let once = expected.clone();
+    expected.extend(once);
+
+    let inputs = ["a", "b"].map(|x| Ok(Input::new(x, &extract[..])));
+    let args = ConvertArgs {
+        chunk_size: Some(300),
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    convert_inputs(
+        inputs.into_iter(),
+        &mut output,
+        &args,
+        &mut Progress::hidden(),
+    )
+    .unwrap();
+    assert!(output == expected);
+
+    // a failure to open a later input still stops the conversion
+    let inputs = [
+        Ok(Input::new("a", &extract[..])),
+        Err(anyhow::anyhow!("missing")),
+    ];
+    let result = convert_inputs(
+        inputs.into_iter(),
+        &mut Vec::new(),
+        &args,
+        &mut Progress::hidden(),
+    );
+    assert_eq!(result.unwrap_err().to_string(), "missing");
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::fs;
+
+use chrono::NaiveDate;
+use common::{extract, records, TempDir};
+use simple_abns::{
+    convert::{convert, run, ConvertArgs},
+    model::SCHEMA_VERSION,
+    output::{CompressionArgs, Format},
+    postgres::{write_binary, write_text, Cell, PostgresWriter},
+};
+
+#[test]
+fn copy_text() {
+    let date = NaiveDate::from_ymd_opt(2024, 11, 27).unwrap();
+    let mut out = Vec::new();
+    let cells = [
+        Cell::Text("SMITH\\JONES\tPTY\nLTD\r"),
+        Cell::Null,
+        Cell::Date(date),
+        Cell::Int(-3),
+        Cell::Text(""),
+    ];
+    write_text(&mut out, &cells).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "SMITH\\\\JONES\\tPTY\\nLTD\\r\t\\N\t2024-11-27\t-3\t\n"
+    );
+}
+
+#[test]
+fn copy_binary() {
+    let date = NaiveDate::from_ymd_opt(1999, 12, 31).unwrap();
+    let mut out = Vec::new();
+    write_binary(
+        &mut out,
+        &[Cell::Text("é"), Cell::Null, Cell::Date(date), Cell::Int(2)],
+    )
+    .unwrap();
+    let expected: &[u8] = &[
+        0, 4, // fields
+        0, 0, 0, 2, 0xc3, 0xa9, // UTF-8 text
+        0xff, 0xff, 0xff, 0xff, // null
+        0, 0, 0, 4, 0xff, 0xff, 0xff, 0xff, // a day before 2000-01-01
+        0, 0, 0, 4, 0, 0, 0, 2,
+    ];
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn tables() {
+    let extract = extract(2000, 0);
+    let records = records(&extract);
+    let other_names: usize = records.iter().map(|x| x.other_names.len()).sum();
+    assert!(other_names > 0);
+
+    let dir = TempDir::new("postgres");
+    for binary in [false, true] {
+        let format = if binary { "binary" } else { "text" };
+        let path = dir.join(format!("{format}.sql"));
+        let mut output = PostgresWriter::create(&path, binary, true, false).unwrap();
+        let args = ConvertArgs {
+            format: Format::Postgres,
+            ..Default::default()
+        };
+        convert(&extract[..], &mut output, &args).unwrap();
+        let written = output.finish().unwrap();
+
+        let files: Vec<_> = written.iter().map(|(path, _)| path.clone()).collect();
+        let counts: Vec<_> = written.iter().map(|(_, x)| x.records).collect();
+        assert_eq!(counts, [0, records.len() as u64, other_names as u64]);
+        for (path, written) in &written {
+            assert_eq!(fs::metadata(path).unwrap().len(), written.bytes, "{path:?}");
+        }
+
+        let script = fs::read_to_string(&files[0]).unwrap();
+        assert!(script.contains(&format!(
+            "\\copy abns FROM '{format}.copy' WITH (FORMAT {format})\n\
+             \\copy abn_other_names FROM '{format}_other_names.copy' WITH (FORMAT {format})\n"
+        )));
+        assert!(script.contains("CREATE TYPE abn_entity_type AS ENUM ('IND', 'PRV'"));
+        assert!(script.contains("    match_key text NOT NULL\n"));
+        assert!(!script.contains("display_name"));
+        assert!(script.contains(&format!(
+            "COMMENT ON TABLE abns IS 'simple-abns {}, record schema version {SCHEMA_VERSION}';\n",
+            env!("CARGO_PKG_VERSION")
+        )));
+
+        let data = fs::read(&files[1]).unwrap();
+        if binary {
+            assert!(data.starts_with(b"PGCOPY\n\xff\r\n\0"));
+            assert!(data.ends_with(&[0xff, 0xff]));
+            continue;
+        }
+        // a field for each column of the table, in order
+        let table = &script[script.find("CREATE TABLE abns").unwrap()..];
+        let columns: Vec<_> = table[..table.find(");").unwrap()]
+            .lines()
+            .skip(1)
+            .map(|x| x.split_whitespace().next().unwrap())
+            .collect();
+        assert_eq!(columns.len(), 20);
+        let data = String::from_utf8(data).unwrap();
+        for (line, record) in data.lines().zip(&records) {
+            let row: Vec<_> = line.split('\t').collect();
+            assert_eq!(row.len(), columns.len());
+            let field = |name| row[columns.iter().position(|x| *x == name).unwrap()];
+            assert_eq!(field("abn"), record.abn);
+            assert_eq!(field("entity_type"), format!("{:?}", record.entity_type));
+            assert_eq!(
+                field("gst_status_since"),
+                record
+                    .gst_status_since
+                    .map_or("\\N".into(), |x| x.to_string())
+            );
+        }
+    }
+}
+
+#[test]
+fn uncompressed_formats() {
+    let dir = TempDir::new("postgres-compression");
+    let options = [
+        CompressionArgs {
+            compression_level: Some(9),
+            ..Default::default()
+        },
+        CompressionArgs {
+            zstd_threads: Some(2),
+            ..Default::default()
+        },
+    ];
+    for (name, format) in [("abns.sql", "PostgreSQL"), ("abns.arrow", "Arrow")] {
+        for compression in options {
+            let args = ConvertArgs {
+                output: Some(dir.join(name)),
+                compression,
+                ..Default::default()
+            };
+            assert_eq!(
+                run(args).unwrap_err().to_string(),
+                format!("{format} output can't be compressed, so --compression-level and --zstd-threads don't apply")
+            );
+            assert!(!dir.join(name).exists());
+        }
+    }
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::TempDir;
+use simple_abns::{
+    emit::emit_record,
+    model::{ABNRecord, EntityName},
+    parser::parse_record,
+    privacy::{PrivacyArgs, Redaction, Redactor},
+};
+
+fn golden(name: &str) -> ABNRecord {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/golden/{name}.xml"));
+    parse_record(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn key_file(dir: &TempDir, name: &str, key: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, key).unwrap();
+    path
+}
+
+fn hash(record: &ABNRecord) -> Option<&str> {
+    match &record.entity_name {
+        EntityName::Redacted { hash } => hash.as_deref(),
+        x => panic!("not redacted: {x:?}"),
+    }
+}
+
+#[test]
+fn drop_names() {
+    let redactor = Redactor::new(&PrivacyArgs {
+        redact_individuals: Some(Redaction::Drop),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let mut record = golden("individual_full_name");
+    record.other_names = golden("mixed_other_names").other_names;
+    redactor.apply(&mut record);
+    assert_eq!(hash(&record), None);
+    assert!(record.other_names.is_empty());
+    assert_eq!(record.abn, golden("individual_full_name").abn);
+
+    let mut record = golden("mixed_other_names");
+    redactor.apply(&mut record);
+    assert_eq!(record, golden("mixed_other_names"));
+}
+
+#[test]
+fn hash_names() {
+    let args = |path: &Path| PrivacyArgs {
+        redact_individuals: Some(Redaction::Hash),
+        hash_key_file: Some(path.to_path_buf()),
+        keep_individual_business_names: true,
+    };
+    let dir = TempDir::new("privacy");
+    let key = key_file(&dir, "key", "0123456789abcdef\n");
+    let other_key = key_file(&dir, "other-key", "fedcba9876543210");
+    let redactor = Redactor::new(&args(&key)).unwrap();
+    let other_redactor = Redactor::new(&args(&other_key)).unwrap();
+
+    let mut a = golden("individual_full_name");
+    a.other_names = golden("mixed_other_names").other_names;
+    let mut b = golden("individual_full_name");
+    let mut c = golden("individual_one_given_name");
+    let mut d = golden("individual_full_name");
+    redactor.apply(&mut a);
+    redactor.apply(&mut b);
+    redactor.apply(&mut c);
+    other_redactor.apply(&mut d);
+
+    assert_eq!(hash(&a).unwrap().len(), 64);
+    assert_eq!(hash(&a), hash(&b));
+    assert_ne!(hash(&a), hash(&c));
+    assert_ne!(hash(&a), hash(&d));
+    assert_eq!(a.other_names, golden("mixed_other_names").other_names);
+}
+
+#[test]
+fn emitted_names() {
+    let dir = TempDir::new("privacy");
+    let key = key_file(&dir, "key", "0123456789abcdef");
+    let dropping = Redactor::new(&PrivacyArgs {
+        redact_individuals: Some(Redaction::Drop),
+        ..Default::default()
+    })
+    .unwrap();
+    let hashing = Redactor::new(&PrivacyArgs {
+        redact_individuals: Some(Redaction::Hash),
+        hash_key_file: Some(key),
+        ..Default::default()
+    })
+    .unwrap();
+
+    // the extract can't leave a name out, so redacted names come back as individuals
+    let mut dropped = golden("individual_full_name");
+    dropping.apply(&mut dropped);
+    let mut hashed = golden("individual_full_name");
+    hashing.apply(&mut hashed);
+    for (record, family) in [
+        (&dropped, "REDACTED".to_string()),
+        (&hashed, hash(&hashed).unwrap().to_string()),
+    ] {
+        let back = parse_record(&emit_record(record)).unwrap();
+        let expected = EntityName::Individual {
+            title: None,
+            given: None,
+            given_2: None,
+            family,
+        };
+        assert_eq!(back.entity_name, expected);
+        assert_eq!(back.abn, record.abn);
+    }
+}
+
+#[test]
+fn invalid_options() {
+    let dir = TempDir::new("privacy");
+    let short = key_file(&dir, "short", "too short");
+    let cases = [
+        PrivacyArgs {
+            redact_individuals: Some(Redaction::Hash),
+            ..Default::default()
+        },
+        PrivacyArgs {
+            redact_individuals: Some(Redaction::Hash),
+            hash_key_file: Some(short.clone()),
+            ..Default::default()
+        },
+        PrivacyArgs {
+            redact_individuals: Some(Redaction::Drop),
+            hash_key_file: Some(short.clone()),
+            ..Default::default()
+        },
+        PrivacyArgs {
+            keep_individual_business_names: true,
+            ..Default::default()
+        },
+    ];
+    for args in cases {
+        assert!(Redactor::new(&args).is_err(), "{args:?}");
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::{
+    io::{self, BufReader, Write},
+    iter,
+    sync::{Arc, Mutex},
+};
+
+use simple_abns::{
+    convert::{convert_inputs, ConvertArgs, Input},
+    progress::{Progress, ProgressArgs},
+};
+
+#[test]
+fn counts() {
+    let mut extract = common::extract(1000, 0);
+    // a broken record just before the end of the transfer
+    let end = extract.len() - "</Transfer>\n".len();
+    extract.splice(end..end, b"<ABR>not a record</ABR>\n".iter().copied());
+
+    let mut progress = Progress::new(&ProgressArgs::default(), extract.len() as u64);
+    let input = BufReader::new(progress.counter().wrap(&extract[..]));
+    let inputs = iter::once(Ok(Input::new("extract.xml", input)));
+    let mut output = Vec::new();
+    let args = ConvertArgs::default();
+    convert_inputs(inputs, &mut output, &args, &mut progress).unwrap();
+
+    let status = progress.status();
+    assert_eq!(status["file"], "extract.xml");
+    assert_eq!(status["records"], 1001);
+    assert_eq!(status["errors"], 1);
+    assert_eq!(status["bytes"], extract.len());
+    assert_eq!(status["total_bytes"], extract.len());
+    assert_eq!(status["eta_seconds"], 0);
+}
+
+/// Somewhere to write progress that can still be read after it's handed over.
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn terminal() {
+    let output = Shared::default();
+    let args = ProgressArgs {
+        progress_log: Some(0),
+    };
+    let mut progress = Progress::writing_to(output.clone(), true, &args, 1000);
+    progress.start_file("extract.xml");
+    progress.records(10);
+    progress.error(&anyhow::anyhow!("invalid record"));
+    progress.records(10);
+    progress.finish();
+
+    // logs and errors clear the status line first, and it's drawn again after them
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<_> = output
+        .lines()
+        .map(|x| x.rsplit("\r\x1b[K").next().unwrap())
+        .collect();
+    assert_eq!(lines.len(), 5, "{output:?}");
+    for i in [0, 2, 4] {
+        let status: serde_json::Value = serde_json::from_str(lines[i]).unwrap();
+        assert_eq!(status["file"], "extract.xml");
+    }
+    assert_eq!(lines[1], "invalid record");
+    assert!(
+        lines[3].starts_with("extract.xml  20 records"),
+        "{output:?}"
+    );
+    // the status line drawn before each log is cleared rather than left in front of it
+    assert!(
+        output.starts_with("\r\x1b[Kextract.xml  10 records"),
+        "{output:?}"
+    );
+    assert_eq!(output.matches("\r\x1b[K{").count(), 2, "{output:?}");
+}
@@ -0,0 +1,164 @@
+use serde_json::json;
+use simple_abns::projection::Projection;
+
+fn projection(fields: &str) -> Projection {
+    fields.parse().unwrap()
+}
+
+#[test]
+fn parsing() {
+    let fields = projection(" abn , entity_name . family : surname,state");
+    assert_eq!(fields.columns(), ["abn", "surname", "state"]);
+    assert!(fields.includes(&["entity_name"]));
+    assert!(fields.includes(&["entity_name", "family"]));
+    assert!(!fields.includes(&["entity_name", "given"]));
+    assert!(!fields.includes(&["postcode"]));
+
+    let error = |fields: &str| fields.parse::<Projection>().unwrap_err().to_string();
+    assert_eq!(error("abn,phone"), "unknown field: phone");
+    assert_eq!(error("abn,Entity_name"), "unknown field: Entity_name");
+    assert_eq!(error("abn,,state"), "empty field in \"\"");
+    assert_eq!(
+        error("entity_name..family"),
+        "empty field in \"entity_name..family\""
+    );
+
+    // fields inside others have to be ones the record has
+    assert!(
+        projection("entity_name.display_name,trust.name,other_names.kind").includes(&["trust"])
+    );
+    assert_eq!(
+        error("entity_name.familly"),
+        "unknown field: entity_name.familly"
+    );
+    assert_eq!(error("trust.knd"), "unknown field: trust.knd");
+    assert_eq!(error("abn.digits"), "unknown field: abn.digits");
+    assert_eq!(
+        error("entity_name.family.first"),
+        "unknown field: entity_name.family.first"
+    );
+
+    // and every output name has to be different
+    assert_eq!(error("abn:"), "empty name in \"abn:\"");
+    assert_eq!(error("abn:x,state:x"), "x is selected more than once");
+    assert_eq!(error("abn,abn"), "abn is selected more than once");
+    assert_eq!(error("state:abn,abn"), "abn is selected more than once");
+    assert_eq!(
+        error("entity_name.name,abn,entity_name.name"),
+        "entity_name.name is selected more than once"
+    );
+    assert_eq!(
+        projection("abn:id,abn,entity_name.name:name,entity_name.family").columns(),
+        ["id", "abn", "name", "entity_name.family"]
+    );
+}
+
+#[test]
+fn nested_paths() {
+    let record = json!({
+        "abn": "53004085616",
+        "entity_name": {"type": "Individual", "given": "MARY", "family": "SMITH"},
+        "state": "VIC",
+    });
+
+    // nested fields keep their nesting unless renamed, in the order given
+    let value = projection("state,entity_name.family,entity_name.given,abn")
+        .apply(&record)
+        .unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "state": "VIC",
+            "entity_name": {"family": "SMITH", "given": "MARY"},
+            "abn": "53004085616",
+        })
+    );
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        r#"{"state":"VIC","entity_name":{"family":"SMITH","given":"MARY"},"abn":"53004085616"}"#
+    );
+
+    let fields = projection("abn:id,entity_name.family:surname,entity_name");
+    assert_eq!(
+        fields.apply(&record).unwrap(),
+        json!({"id": "53004085616", "surname": "SMITH", "entity_name": record["entity_name"]})
+    );
+
+    // missing fields are null so every line has the same keys
+    let fields = projection("entity_name.title,postcode,trust.kind");
+    assert_eq!(
+        fields.apply(&record).unwrap(),
+        json!({"entity_name": {"title": null}, "postcode": null, "trust": {"kind": null}})
+    );
+    assert_eq!(fields.row(&record).unwrap(), vec![json!(null); 3]);
+}
+
+#[test]
+fn list_paths() {
+    let record = json!({
+        "abn": "53004085616",
+        "other_names": [
+            {"name": "SMITH PLUMBING", "kind": "BN"},
+            {"name": "SMITHS", "kind": "TRD"},
+        ],
+    });
+
+    let fields = projection("abn,other_names.name:names,other_names.kind");
+    assert_eq!(
+        fields.apply(&record).unwrap(),
+        json!({
+            "abn": "53004085616",
+            "names": ["SMITH PLUMBING", "SMITHS"],
+            "other_names": {"kind": ["BN", "TRD"]},
+        })
+    );
+    assert_eq!(fields.columns(), ["abn", "names", "other_names.kind"]);
+    assert_eq!(
+        fields.row(&record).unwrap(),
+        [
+            json!("53004085616"),
+            json!(["SMITH PLUMBING", "SMITHS"]),
+            json!(["BN", "TRD"]),
+        ]
+    );
+
+    // whole lists, and fields missing from some of their elements
+    let record = json!({"other_names": [{"name": "SMITHS"}, {"kind": "TRD"}]});
+    assert_eq!(
+        projection("other_names").apply(&record).unwrap(),
+        json!({"other_names": record["other_names"]})
+    );
+    assert_eq!(
+        projection("other_names.kind").row(&record).unwrap(),
+        [json!([null, "TRD"])]
+    );
+    assert_eq!(
+        projection("other_names.name").row(&json!({})).unwrap(),
+        [json!(null)]
+    );
+}
+
+#[test]
+fn legacy_names() {
+    let error = |fields: &str, legacy_names| {
+        projection(fields)
+            .check_names(legacy_names)
+            .err()
+            .map(|x| x.to_string())
+    };
+    assert_eq!(error("abn,other_names.name", false), None);
+    assert_eq!(error("abn,business_names,trade_names:names", true), None);
+    assert_eq!(error("abn,state", true), None);
+    assert_eq!(
+        error("abn,business_names", false).unwrap(),
+        "business_names is only written with --legacy-names"
+    );
+    assert_eq!(
+        error("trade_names:names", false).unwrap(),
+        "trade_names is only written with --legacy-names"
+    );
+    assert_eq!(
+        error("abn,other_names.kind", true).unwrap(),
+        "other_names isn't written with --legacy-names"
+    );
+}
@@ -0,0 +1,225 @@
+//! The schema in `schema::schema()` is checked against a copy saved for each `SCHEMA_VERSION` in
+//! `tests/schema/v{N}.json`, so it can't change without the version changing too, and records
+//! written with every option are checked against the schema.
+
+mod common;
+
+use std::{fs, path::Path};
+
+use chrono::NaiveDate;
+use common::{extract, TempDir};
+use serde_json::Value;
+use simple_abns::{
+    convert::{convert, ConvertArgs},
+    model::{EntityName, EntityType, Status, TrustKind, SCHEMA_VERSION},
+    privacy::{PrivacyArgs, Redaction},
+    schema::schema,
+};
+
+#[test]
+fn versioned() {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("tests/schema/v{SCHEMA_VERSION}.json"));
+    let Ok(saved) = fs::read_to_string(&path) else {
+        panic!("no saved schema for version {SCHEMA_VERSION}, run `simple-abns schema > {path:?}`");
+    };
+    let saved: Value = serde_json::from_str(&saved).unwrap();
+    assert!(
+        saved == schema(),
+        "the schema has changed since version {SCHEMA_VERSION} was saved, so bump SCHEMA_VERSION \
+         and save the new schema with `simple-abns schema`"
+    );
+}
+
+#[test]
+fn enum_labels() {
+    // the schema, Arrow dictionaries and PostgreSQL enums are built from these, so they have to
+    // be what's written
+    for (i, x) in Status::ALL.into_iter().enumerate() {
+        assert_eq!(x as usize, i);
+        assert_eq!(serde_json::to_value(x).unwrap(), x.label());
+    }
+    for (i, x) in TrustKind::ALL.into_iter().enumerate() {
+        assert_eq!(x as usize, i);
+        assert_eq!(serde_json::to_value(x).unwrap(), x.label());
+    }
+    for (i, x) in EntityType::ALL.into_iter().enumerate() {
+        assert_eq!(x as usize, i);
+        assert_eq!(serde_json::to_value(x).unwrap(), format!("{x:?}"));
+    }
+    let names = [
+        EntityName::Individual {
+            title: None,
+            given: None,
+            given_2: None,
+            family: "SMITH".into(),
+        },
+        EntityName::NonIndividual { name: "X".into() },
+        EntityName::Redacted { hash: None },
+    ];
+    assert_eq!(names.len(), EntityName::TYPES.len());
+    for (i, x) in names.iter().enumerate() {
+        assert_eq!(x.type_index(), i);
+        assert_eq!(serde_json::to_value(x).unwrap()["type"], x.type_name());
+    }
+}
+
+/// Checks the parts of JSON Schema that `schema()` uses, returning where `value` doesn't match.
+fn validate(value: &Value, schema: &Value, root: &Value, path: &str) -> Result<(), String> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.strip_prefix("#/$defs/").unwrap();
+        return validate(value, &root["$defs"][name], root, path);
+    }
+    if let Some(options) = schema["oneOf"].as_array() {
+        let matches = options
+            .iter()
+            .filter(|x| validate(value, x, root, path).is_ok())
+            .count();
+        return match matches {
+            1 => Ok(()),
+            n => Err(format!("{path} matches {n} of oneOf")),
+        };
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            return Err(format!("{path} isn't {expected}"));
+        }
+    }
+    if let Some(options) = schema["enum"].as_array() {
+        if !options.contains(value) {
+            return Err(format!("{path} isn't one of {options:?}"));
+        }
+    }
+    let matches_type = match schema["type"].as_str() {
+        None => true,
+        Some("string") => value.is_string(),
+        Some("object") => value.is_object(),
+        Some("array") => value.is_array(),
+        Some(x) => panic!("unexpected type {x}"),
+    };
+    if !matches_type {
+        return Err(format!("{path} isn't a {}", schema["type"]));
+    }
+    if schema["format"] == "date" {
+        let date = value.as_str().unwrap();
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("{path} isn't a date"))?;
+    }
+    if schema["pattern"].is_string() {
+        // only the ABN has a pattern
+        let abn = value.as_str().unwrap();
+        if abn.len() != 11 || !abn.bytes().all(|x| x.is_ascii_digit()) {
+            return Err(format!("{path} isn't an ABN"));
+        }
+    }
+    if let Some(items) = schema.get("items") {
+        for (i, item) in value.as_array().unwrap().iter().enumerate() {
+            validate(item, items, root, &format!("{path}[{i}]"))?;
+        }
+    }
+    if let Some(object) = value.as_object().filter(|_| schema["type"] == "object") {
+        for key in schema["required"].as_array().into_iter().flatten() {
+            if !object.contains_key(key.as_str().unwrap()) {
+                return Err(format!("{path} is missing {key}"));
+            }
+        }
+        for (key, value) in object {
+            match schema["properties"].get(key) {
+                Some(property) => validate(value, property, root, &format!("{path}.{key}"))?,
+                None if schema["additionalProperties"] == false => {
+                    return Err(format!("{path} has unexpected {key}"))
+                }
+                None => (),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn records_match() {
+    let extract = extract(2000, 0);
+    let dir = TempDir::new("schema");
+    let key = dir.join("key");
+    fs::write(&key, "0123456789abcdef").unwrap();
+
+    let schema = schema();
+    let options = [
+        ConvertArgs::default(),
+        ConvertArgs {
+            legacy_names: true,
+            match_key: true,
+            display_name: true,
+            ..Default::default()
+        },
+        ConvertArgs {
+            privacy: PrivacyArgs {
+                redact_individuals: Some(Redaction::Drop),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ConvertArgs {
+            privacy: PrivacyArgs {
+                redact_individuals: Some(Redaction::Hash),
+                hash_key_file: Some(key.clone()),
+                ..Default::default()
+            },
+            display_name: true,
+            ..Default::default()
+        },
+    ];
+    for args in &options {
+        let mut output = Vec::new();
+        convert(&extract[..], &mut output, args).unwrap();
+        for line in String::from_utf8(output).unwrap().lines() {
+            let record: Value = serde_json::from_str(line).unwrap();
+            if let Err(e) = validate(&record, &schema, &schema, "record") {
+                panic!("{e} in {line}");
+            }
+        }
+    }
+}
+
+#[test]
+fn golden_records_match() {
+    let schema = schema();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    for path in fs::read_dir(dir).unwrap() {
+        let path = path.unwrap().path();
+        if path.extension().is_some_and(|x| x == "json") {
+            let record: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+            if let Err(e) = validate(&record, &schema, &schema, "record") {
+                panic!("{e} in {path:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn rejects_other_records() {
+    let schema = schema();
+    let record: Value = serde_json::from_str(
+        r#"{"abn":"51824753556","status":"Active","status_since":"2000-01-01","last_updated":"2024-01-01","entity_name":{"type":"NonIndividual","name":"X"},"entity_type":"PRV"}"#,
+    )
+    .unwrap();
+    assert_eq!(validate(&record, &schema, &schema, "record"), Ok(()));
+
+    for (key, value) in [
+        ("abn", "5182475355".into()),
+        ("status", "Suspended".into()),
+        ("entity_type", "XYZ".into()),
+        ("last_updated", "2024-13-01".into()),
+        (
+            "entity_name",
+            serde_json::json!({"type": "Individual", "name": "X"}),
+        ),
+        ("new_field", 1.into()),
+    ] {
+        let mut record = record.clone();
+        record[key] = value;
+        assert!(
+            validate(&record, &schema, &schema, "record").is_err(),
+            "{key}"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use std::{
+    fs,
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    path::Path,
+    sync::{atomic::AtomicBool, Arc},
+    thread::{self, JoinHandle},
+};
+
+use common::{extract, indexed, records, TempDir};
+use serde_json::Value;
+use simple_abns::{
+    index::{index_path, Dataset, Index},
+    model::{ABNRecord, Status},
+    serve::{serve, stop},
+};
+use tiny_http::Server;
+
+/// A dataset served on a free port in the background, until it's dropped.
+struct Running {
+    addr: SocketAddr,
+    server: Arc<Server>,
+    stopping: Arc<AtomicBool>,
+    thread: Option<JoinHandle<anyhow::Result<()>>>,
+}
+
+fn start(data: &Path) -> Running {
+    let index = Index::load(&index_path(data)).unwrap();
+    let datasets = (0..2).map(|_| Dataset::open(data).unwrap()).collect();
+    let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+    let stopping = Arc::new(AtomicBool::new(false));
+    let addr = server.server_addr().to_ip().unwrap();
+    let thread = thread::spawn({
+        let (server, stopping) = (server.clone(), stopping.clone());
+        move || serve(&server, &stopping, &index, datasets)
+    });
+    Running {
+        addr,
+        server,
+        stopping,
+        thread: Some(thread),
+    }
+}
+
+impl Running {
+    fn stop(&mut self) -> anyhow::Result<()> {
+        stop(&self.server, &self.stopping);
+        match self.thread.take() {
+            Some(thread) => thread.join().unwrap(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn get(addr: SocketAddr, path: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, serde_json::from_str(body).unwrap())
+}
+
+fn json(record: &ABNRecord) -> Value {
+    serde_json::to_value(record).unwrap()
+}
+
+#[test]
+fn lookups() {
+    let dir = TempDir::new("serve");
+    let records = records(&extract(500, 0));
+    let data = indexed(&dir, &records);
+    let server = start(&data);
+    let addr = server.addr;
+
+    let record = &records[42];
+    let abn = &record.abn;
+    assert_eq!(get(addr, &format!("/abn/{abn}")), (200, json(record)));
+    let spaced = format!(
+        "/abn/{}%20{}%20{}%20{}",
+        &abn[..2],
+        &abn[2..5],
+        &abn[5..8],
+        &abn[8..]
+    );
+    assert_eq!(get(addr, &spaced), (200, json(record)));
+    assert_eq!(get(addr, "/abn/00000000000").0, 404);
+
+    let record = records
+        .iter()
+        .find(|x| x.asic_number.is_some() && x.status == Status::Active)
+        .unwrap();
+    let acn = record.asic_number.as_ref().unwrap();
+    let (status, found) = get(addr, &format!("/acn/{acn}"));
+    assert_eq!(status, 200);
+    assert_eq!(found["asic_number"], acn.as_str());
+    assert_eq!(get(addr, "/nowhere").0, 404);
+}
+
+#[test]
+fn search() {
+    let dir = TempDir::new("serve-search");
+    let records = records(&extract(500, 0));
+    let data = indexed(&dir, &records);
+    let server = start(&data);
+    let addr = server.addr;
+
+    let record = records
+        .iter()
+        .find(|x| x.other_names.iter().any(|x| x.name.len() > 8))
+        .unwrap();
+    let name = &record.other_names[0].name;
+    let query: String = form_urlencoded::Serializer::new(String::new())
+        .append_pair("q", name)
+        .append_pair("limit", "5")
+        .finish();
+    let (status, hits) = get(addr, &format!("/search?{query}"));
+    assert_eq!(status, 200);
+    let hits = hits.as_array().unwrap();
+    assert!(!hits.is_empty() && hits.len() <= 5);
+    assert!(hits.iter().any(|x| x["abn"] == record.abn.as_str()));
+
+    for path in [
+        "/search",
+        "/search?q=smith&status=bogus",
+        "/search?q=smith&type=XYZ",
+        "/search?q=smith&postcode=30a0",
+        "/search?q=smith&limit=-1",
+        "/abn/%FF",
+    ] {
+        let (status, body) = get(addr, path);
+        assert_eq!(status, 400, "{path}");
+        assert!(body["error"].is_string(), "{path}");
+    }
+}
+
+#[test]
+fn stale_index() {
+    let dir = TempDir::new("serve-stale");
+    let records = records(&extract(50, 0));
+    let data = indexed(&dir, &records);
+    let server = start(&data);
+    let addr = server.addr;
+
+    // the dataset is rewritten without rebuilding its index
+    let lines: String = records
+        .iter()
+        .map(|x| serde_json::to_string(x).unwrap()[1..].to_string() + "\n")
+        .collect();
+    fs::write(&data, lines).unwrap();
+    let (status, body) = get(addr, &format!("/abn/{}", records[1].abn));
+    assert_eq!(status, 500);
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("is the index stale?"));
+}
+
+#[test]
+fn shutdown() {
+    let dir = TempDir::new("serve-shutdown");
+    let records = records(&extract(10, 0));
+    let data = indexed(&dir, &records);
+    let mut server = start(&data);
+    let addr = server.addr;
+    assert_eq!(get(addr, &format!("/abn/{}", records[0].abn)).0, 200);
+
+    // serve only returns once every thread has stopped, not just the one that's woken first
+    server.stop().unwrap();
+}
@@ -0,0 +1,144 @@
+use simple_abns::{
+    parser::parse_record,
+    xml::{escape, unescape, Event, Reader},
+};
+
+/// The events of a document written out briefly, e.g. `<GST status=ACT>`, `text` and `</GST>`.
+fn events(input: &str) -> anyhow::Result<Vec<String>> {
+    let mut reader = Reader::new(input);
+    let mut events = Vec::new();
+    while let Some(event) = reader.read_event()? {
+        events.push(match event {
+            Event::Start { name, attributes } => {
+                let attributes = attributes
+                    .map(|x| x.map(|(key, value)| format!(" {key}={value}")))
+                    .collect::<anyhow::Result<String>>()?;
+                format!("<{name}{attributes}>")
+            }
+            Event::End { name } => format!("</{name}>"),
+            Event::Text(x) => x.into_owned(),
+        });
+    }
+    Ok(events)
+}
+
+fn error(input: &str) -> String {
+    events(input).unwrap_err().to_string()
+}
+
+#[test]
+fn elements() {
+    let input = "<?xml version=\"1.0\"?>\n<ABR a='1' b = \"x > y\">\n\t<ABN>1</ABN><!-- a comment -->\n</ABR >";
+    assert_eq!(
+        events(input).unwrap(),
+        ["<ABR a=1 b=x > y>", "<ABN>", "1", "</ABN>", "</ABR>"]
+    );
+
+    // empty elements are a start and an end, with or without a space before the slash
+    assert_eq!(
+        events("<ABR><GST status=\"ACT\" /><DGR/></ABR>").unwrap(),
+        [
+            "<ABR>",
+            "<GST status=ACT>",
+            "</GST>",
+            "<DGR>",
+            "</DGR>",
+            "</ABR>"
+        ]
+    );
+}
+
+#[test]
+fn text() {
+    let input = "<A><![CDATA[SMITH & <SONS>]]> PTY &amp; LTD</A>";
+    assert_eq!(
+        events(input).unwrap(),
+        ["<A>", "SMITH & <SONS>", " PTY & LTD", "</A>"]
+    );
+    assert_eq!(events("<A><![CDATA[]]></A>").unwrap(), ["<A>", "", "</A>"]);
+
+    // character references in text and attribute values
+    let input = "<A name=\"&quot;O&apos;BRIEN&quot; &#40;AUST&#x29;\">&lt;&#233;&#xE9;&gt;</A>";
+    assert_eq!(
+        events(input).unwrap(),
+        ["<A name=\"O'BRIEN\" (AUST)>", "<éé>", "</A>"]
+    );
+    for (text, reference) in [
+        ("&nbsp;", "&nbsp;"),
+        ("&#0;", "&#0;"),
+        ("&#xD800;", "&#xD800;"),
+        ("&#x;", "&#x;"),
+        ("&#12a;", "&#12a;"),
+    ] {
+        assert_eq!(
+            unescape(text).unwrap_err().to_string(),
+            format!("unknown reference: {reference}")
+        );
+    }
+    assert_eq!(
+        unescape("SMITH & SONS").unwrap_err().to_string(),
+        "unterminated reference"
+    );
+
+    let text = "SMITH & \"SONS\" <'AUST'>\r\n";
+    assert_eq!(
+        escape(text),
+        "SMITH &amp; &quot;SONS&quot; &lt;&apos;AUST&apos;&gt;&#13;&#10;"
+    );
+    assert_eq!(unescape(&escape(text)).unwrap(), text);
+}
+
+#[test]
+fn unterminated() {
+    for (input, message) in [
+        ("<ABR", "unterminated start tag"),
+        ("<ABR status=\"ACT>", "unterminated start tag"),
+        ("<ABR></ABR", "unterminated end tag"),
+        ("<ABR><!-- comment", "unterminated comment"),
+        (
+            "<?xml version=\"1.0\"",
+            "unterminated processing instruction",
+        ),
+        ("<A><![CDATA[SMITH", "unterminated cdata"),
+        ("<A b=\"1>", "unterminated start tag"),
+        ("<A b='1\">", "unterminated start tag"),
+        ("<A b=1>", "unquoted attribute value"),
+        ("<A b>", "attribute without a value"),
+        ("<!DOCTYPE ABR>", "unsupported markup declaration"),
+        ("<>", "invalid name: \"\""),
+        ("</>", "invalid name: \"\""),
+        ("<A>&amp</A>", "unterminated reference"),
+    ] {
+        assert_eq!(error(input), message, "{input}");
+    }
+}
+
+#[test]
+fn mismatched_end_tags() {
+    // the reader leaves nesting to the parser, which checks each end tag against its path
+    assert_eq!(
+        events("<ABR><ABN>1</EntityType></ABR>").unwrap(),
+        ["<ABR>", "<ABN>", "1", "</EntityType>", "</ABR>"]
+    );
+
+    let record = "<ABR recordLastUpdatedDate=\"20240412\" replaced=\"N\"><ABN status=\"ACT\" ABNStatusFromDate=\"19991101\">88712649015</ABN><EntityType><EntityTypeInd>PRV</EntityTypeInd><EntityTypeText>Australian Private Company</EntityTypeText></EntityType><MainEntity><NonIndividualName type=\"MN\"><NonIndividualNameText>EXAMPLE HOLDINGS PTY LTD</NonIndividualNameText></NonIndividualName></MainEntity><GST status=\"ACT\" GSTStatusFromDate=\"20000701\" /></ABR>";
+    assert!(parse_record(record).is_ok());
+    for (from, to, message) in [
+        (
+            "</ABN>",
+            "</abn>",
+            "in [\"ABR\", \"ABN\"]: unexpected end of abn",
+        ),
+        (
+            "</MainEntity>",
+            "</NonIndividualName>",
+            "in [\"ABR\", \"MainEntity\"]: unexpected end of NonIndividualName",
+        ),
+        ("</ABR>", "</ABR></ABR>", "in []: unexpected end of ABR"),
+    ] {
+        let error = parse_record(&record.replace(from, to)).unwrap_err();
+        assert_eq!(format!("{error:#}"), message, "{to}");
+    }
+    let error = parse_record(&record.replace("</ABR>", "")).unwrap_err();
+    assert_eq!(error.to_string(), "unexpected end of record");
+}